@@ -103,15 +103,8 @@ const LOGO_HALF_SIZE: Vec2 = Vec2::new(260., 65.);
 
 /// Make the logo bounce.
 fn bounce_around(
-    mut logos: Populated<
-        (
-            &StateData<LogoState>,
-            &mut Sprite,
-            &mut Transform,
-            &mut Velocity,
-        ),
-        With<Sprite>,
-    >,
+    // `StateQuery` lets us iterate only over logos in a specific state.
+    mut logos: StateQuery<LogoState, (&mut Sprite, &mut Transform, &mut Velocity)>,
     camera: Single<&Projection>,
     time: Res<Time>,
 ) {
@@ -119,12 +112,7 @@ fn bounce_around(
         return;
     };
     let delta = time.delta_secs();
-    for (state, mut sprite, mut transform, mut velocity) in logos.iter_mut() {
-        // Ignore logos which are in the disabled state.
-        if *state.current() == LogoState::Disabled {
-            continue;
-        }
-
+    for (_, (mut sprite, mut transform, mut velocity)) in logos.iter_in_mut(LogoState::Enabled) {
         transform.translation += velocity.0.extend(0.) * delta;
 
         let logo_pos = transform.translation.xy();
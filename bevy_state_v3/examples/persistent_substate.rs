@@ -34,54 +34,16 @@ enum InnerState {
     Disabled,
 }
 
-#[derive(Default, PartialEq, Debug, Clone)]
+/// Deriving with the `retain` option makes the substate remember its value
+/// while the parent state is disabled, instead of resetting to default.
+#[derive(State, Default, PartialEq, Debug, Clone)]
+#[dependency(InnerState = InnerState::Enabled, retain)]
 enum OuterState {
     #[default]
     Enabled,
     Disabled,
 }
 
-impl State for OuterState {
-    type Dependencies = InnerState;
-    type Update = PersistentUpdate<Self>;
-    type Repr = Option<Self>;
-
-    fn update(
-        state: &mut StateData<Self>,
-        inner: StateSetData<'_, Self::Dependencies>,
-    ) -> Self::Repr {
-        match (inner.current(), &state.update().value) {
-            (InnerState::Enabled, value) => Some(value.clone()),
-            (InnerState::Disabled, _) => None,
-        }
-    }
-}
-
-#[derive(Default, Debug)]
-struct PersistentUpdate<S: State> {
-    should_update: bool,
-    value: S,
-}
-
-impl<S: State + Default> StateUpdate for PersistentUpdate<S> {
-    fn should_update(&self) -> bool {
-        self.should_update
-    }
-
-    fn post_update(&mut self) {
-        self.should_update = false;
-    }
-}
-
-impl<S: State> From<S> for PersistentUpdate<S> {
-    fn from(value: S) -> Self {
-        PersistentUpdate {
-            should_update: true,
-            value,
-        }
-    }
-}
-
 /// User controls.
 fn user_input(
     mut commands: Commands,
@@ -1,8 +1,8 @@
-//! This example showcases how a custom update data structure can make
+//! This example showcases how the stack update backend can make
 //! a state work on a stack with push and pop operations.
 
 use bevy::prelude::*;
-use bevy_state_v3::{commands::state_target_entity, prelude::*};
+use bevy_state_v3::prelude::*;
 
 fn main() {
     App::new()
@@ -35,130 +35,7 @@ impl State for MyState {
     type Repr = Option<Self>;
 
     fn update(state: &mut StateData<Self>, _: StateSetData<'_, Self::Dependencies>) -> Self::Repr {
-        state.update()
-    }
-}
-
-/// Helper enum for stack operations.
-#[derive(Debug)]
-enum StackOp<S> {
-    /// Adds a value to top of the stack.
-    Push(S),
-    /// Removes a value from top of the stack.
-    Pop,
-}
-
-/// Stack update data structure for states.
-#[derive(Debug)]
-pub struct StackUpdate<S: State> {
-    /// The stack except the top value, which is stored as the `current` state.
-    stack: Vec<S>,
-    /// Pending operation on the stack.
-    op: Option<StackOp<S>>,
-}
-
-impl<S: State> Default for StackUpdate<S> {
-    fn default() -> Self {
-        Self {
-            stack: Default::default(),
-            op: Default::default(),
-        }
-    }
-}
-
-impl<S: State> StateUpdate for StackUpdate<S> {
-    fn should_update(&self) -> bool {
-        self.op.is_some()
-    }
-
-    fn post_update(&mut self) {
-        self.op.take();
-    }
-}
-
-/// Helper for updating the state data.
-pub trait StackUpdateData<S: State<Update = StackUpdate<S>>> {
-    /// Updates the stack state.
-    fn update(&mut self) -> Option<S>;
-}
-
-impl<S: State<Repr = Option<S>, Update = StackUpdate<S>>> StackUpdateData<S> for StateData<S> {
-    fn update(&mut self) -> Option<S> {
-        // We assume there are no parent states, which means this value being present is the only reason state is being updated.
-        let op = self.update_mut().op.take().unwrap();
-        match op {
-            StackOp::Push(new) => {
-                if let Some(current) = self.current().clone() {
-                    self.update_mut().stack.push(current);
-                }
-                Some(new)
-            }
-            StackOp::Pop => self.update_mut().stack.pop(),
-        }
-    }
-}
-
-/// Command for updating the stack state.
-struct StackOpCommand<S> {
-    /// Global or local state.
-    local: Option<Entity>,
-    /// Operation we want to perform.
-    op: StackOp<S>,
-}
-
-impl<S> Command for StackOpCommand<S>
-where
-    S: State<Repr = Option<S>, Update = StackUpdate<S>>,
-{
-    fn apply(self, world: &mut World) {
-        let Some(entity) = state_target_entity(world, self.local) else {
-            return;
-        };
-        let mut entity = world.entity_mut(entity);
-        let Some(mut state_data) = entity.get_mut::<StateData<S>>() else {
-            warn!(
-                "Missing state data component for {}.",
-                disqualified::ShortName::of::<S>()
-            );
-            return;
-        };
-        state_data.update_mut().op = Some(self.op);
-    }
-}
-
-/// Commands extension for requesting stack operations.
-pub trait StackStateExt {
-    /// Pushes a new state to the top of the stack.
-    fn push_state<S>(&mut self, local: Option<Entity>, value: S)
-    where
-        S: State<Repr = Option<S>, Update = StackUpdate<S>>;
-
-    /// Pops the top state from the stack.
-    /// Repeats the current state if no more states are left on the stack.
-    fn pop_state<S>(&mut self, local: Option<Entity>)
-    where
-        S: State<Repr = Option<S>, Update = StackUpdate<S>>;
-}
-
-impl StackStateExt for Commands<'_, '_> {
-    fn push_state<S>(&mut self, local: Option<Entity>, value: S)
-    where
-        S: State<Repr = Option<S>, Update = StackUpdate<S>>,
-    {
-        self.queue(StackOpCommand {
-            local,
-            op: StackOp::Push(value),
-        });
-    }
-
-    fn pop_state<S>(&mut self, local: Option<Entity>)
-    where
-        S: State<Repr = Option<S>, Update = StackUpdate<S>>,
-    {
-        self.queue(StackOpCommand {
-            local,
-            op: StackOp::<S>::Pop,
-        });
+        state.apply_stack_op()
     }
 }
 
@@ -224,7 +101,7 @@ fn update_text(
     mut text: Single<&mut Text, With<StateLabel>>,
 ) {
     let mut content = String::new();
-    for state in state.update().stack.iter().chain(state.current().iter()) {
+    for state in state.stack() {
         content.push_str(&format!("{:?}\n", state));
     }
     text.0 = content;
@@ -27,6 +27,8 @@ fn main() {
         .add_observer(setup)
         .add_observer(on_enter)
         .add_observer(on_reexit)
+        // Systems can also be registered for a specific value only.
+        .add_state_observer(OnEnter::<MyState>::when(MyState::Moe), last_state)
         .add_systems(Update, user_input)
         // Initialize state last, after all systems and observers are registered.
         .init_state(None, MyState::Eeny)
@@ -112,6 +114,12 @@ fn on_reexit(
     update_log(log, text, transition);
 }
 
+/// System that runs only when [`MyState::Moe`] is entered.
+fn last_state(mut label: Single<(&mut Text, &mut TransitionLog)>) {
+    let (text, log) = &mut *label;
+    update_log(log, text, "Reached the last state!".to_string());
+}
+
 fn update_log(log: &mut TransitionLog, text: &mut Text, transition: String) {
     log.0.insert(0, transition);
     log.0.truncate(10);
@@ -8,8 +8,8 @@ extern crate proc_macro;
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
 use syn::{
-    DeriveInput, Ident, ImplGenerics, Pat, Path, Result, TypeGenerics, WhereClause,
-    parse_macro_input, spanned::Spanned,
+    Data, DeriveInput, Fields, Ident, ImplGenerics, Pat, Path, Result, Type, TypeGenerics,
+    WhereClause, meta::ParseNestedMeta, parse_macro_input, spanned::Spanned, token,
 };
 
 pub(crate) fn bevy_state_path() -> Path {
@@ -17,42 +17,183 @@ pub(crate) fn bevy_state_path() -> Path {
     syn::parse("bevy_state_v3".parse::<TokenStream>().unwrap()).unwrap()
 }
 
+/// Condition on dependency states under which the derived state exists.
+enum Condition {
+    /// Dependency state matches the pattern.
+    Matches { ty: Path, value: Box<Pat> },
+    /// All conditions are met.
+    All(Vec<Condition>),
+    /// Any condition is met.
+    Any(Vec<Condition>),
+    /// Condition is not met.
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    /// Parses a single condition from within the attribute.
+    fn parse(nested: ParseNestedMeta) -> Result<Self> {
+        let combinator = nested.input.peek(token::Paren);
+        if combinator && nested.path.is_ident("all") {
+            return Ok(Self::All(Self::parse_list(&nested)?));
+        }
+        if combinator && nested.path.is_ident("any") {
+            return Ok(Self::Any(Self::parse_list(&nested)?));
+        }
+        if combinator && nested.path.is_ident("not") {
+            let mut conditions = Self::parse_list(&nested)?;
+            if conditions.len() != 1 {
+                return Err(nested.error("`not` expects exactly one condition"));
+            }
+            return Ok(Self::Not(Box::new(conditions.pop().unwrap())));
+        }
+        let ty = nested.path.clone();
+        let value = Box::new(Pat::parse_multi(nested.value()?)?);
+        Ok(Self::Matches { ty, value })
+    }
+
+    /// Parses conditions of a combinator.
+    fn parse_list(nested: &ParseNestedMeta) -> Result<Vec<Self>> {
+        let mut conditions = vec![];
+        nested.parse_nested_meta(|nested| {
+            conditions.push(Self::parse(nested)?);
+            Ok(())
+        })?;
+        Ok(conditions)
+    }
+
+    /// Collects dependency types in order of appearance, without duplicates.
+    fn collect_types(&self, types: &mut Vec<Path>) {
+        match self {
+            Self::Matches { ty, .. } => {
+                if find_type(types, ty).is_none() {
+                    types.push(ty.clone());
+                }
+            }
+            Self::All(conditions) | Self::Any(conditions) => {
+                conditions.iter().for_each(|c| c.collect_types(types));
+            }
+            Self::Not(condition) => condition.collect_types(types),
+        }
+    }
+
+    /// Creates a boolean expression which evaluates the condition.
+    /// Dependencies are expected to be bound to `dependency_{index}` variables.
+    fn to_expr(&self, types: &[Path]) -> proc_macro2::TokenStream {
+        match self {
+            Self::Matches { ty, value } => {
+                let dependency = format_ident!("dependency_{}", find_type(types, ty).unwrap());
+                quote! { matches!(#dependency.current(), #value) }
+            }
+            Self::All(conditions) => conditions
+                .iter()
+                .map(|c| c.to_expr(types))
+                .reduce(|a, b| quote! { #a && #b })
+                .map_or_else(|| quote! { true }, |c| quote! { (#c) }),
+            Self::Any(conditions) => conditions
+                .iter()
+                .map(|c| c.to_expr(types))
+                .reduce(|a, b| quote! { #a || #b })
+                .map_or_else(|| quote! { false }, |c| quote! { (#c) }),
+            Self::Not(condition) => {
+                let condition = condition.to_expr(types);
+                quote! { !#condition }
+            }
+        }
+    }
+}
+
+/// Returns index of the type in the list.
+fn find_type(types: &[Path], ty: &Path) -> Option<usize> {
+    let ty = quote!(#ty).to_string();
+    types
+        .iter()
+        .position(|other| quote!(#other).to_string() == ty)
+}
+
 struct Dependency {
-    ty: Path,
-    value: Pat,
+    condition: Condition,
+    retain: bool,
 }
 
+/// Parses all `#[dependency]` attributes.
+/// Conditions from multiple attributes have to be met at once.
 fn parse_sources_attr(ast: &DeriveInput) -> Result<Option<Dependency>> {
-    let mut result = ast
-        .attrs
-        .iter()
-        .filter(|a| a.path().is_ident("dependency"))
-        .map(|meta| {
-            let mut source = None;
-            let value = meta.parse_nested_meta(|nested| {
-                let ty = nested.path.clone();
-                let value = Pat::parse_multi(nested.value()?)?;
-                source = Some(Dependency { ty, value });
-                Ok(())
-            });
-            match source {
-                Some(value) => Ok(value),
-                None => match value {
-                    Ok(_) => Err(syn::Error::new(ast.span(), "couldn't parse dependency")),
-                    Err(e) => Err(e),
-                },
+    let mut conditions = vec![];
+    let mut retain = false;
+    for attr in ast.attrs.iter().filter(|a| a.path().is_ident("dependency")) {
+        let len = conditions.len();
+        attr.parse_nested_meta(|nested| {
+            if nested.path.is_ident("retain") {
+                retain = true;
+                return Ok(());
             }
-        })
-        .collect::<Result<Vec<_>>>()?;
+            conditions.push(Condition::parse(nested)?);
+            Ok(())
+        })?;
+        if conditions.len() == len {
+            return Err(syn::Error::new(attr.span(), "couldn't parse dependency"));
+        }
+    }
+
+    let condition = match conditions.len() {
+        0 => return Ok(None),
+        1 => conditions.pop().unwrap(),
+        _ => Condition::All(conditions),
+    };
+    Ok(Some(Dependency { condition, retain }))
+}
 
-    if result.len() > 1 {
+struct Computed {
+    from: Vec<Type>,
+    with: Path,
+    optional: bool,
+}
+
+/// Parses the `#[computed]` attribute.
+fn parse_computed_attr(ast: &DeriveInput) -> Result<Option<Computed>> {
+    let mut attrs = ast.attrs.iter().filter(|a| a.path().is_ident("computed"));
+    let Some(attr) = attrs.next() else {
+        return Ok(None);
+    };
+    if let Some(attr) = attrs.next() {
         return Err(syn::Error::new(
-            ast.span(),
-            "only one state is allowed as dependency",
+            attr.span(),
+            "only one `computed` attribute is allowed",
         ));
     }
 
-    Ok(result.pop())
+    let mut from = None;
+    let mut with = None;
+    let mut optional = true;
+    attr.parse_nested_meta(|nested| {
+        if nested.path.is_ident("from") {
+            from = Some(match nested.value()?.parse::<Type>()? {
+                Type::Tuple(tuple) => tuple.elems.into_iter().collect(),
+                ty => vec![ty],
+            });
+            Ok(())
+        } else if nested.path.is_ident("with") {
+            with = Some(nested.value()?.parse::<Path>()?);
+            Ok(())
+        } else if nested.path.is_ident("non_optional") {
+            optional = false;
+            Ok(())
+        } else {
+            Err(nested.error("expected `from`, `with` or `non_optional`"))
+        }
+    })?;
+
+    match (from, with) {
+        (Some(from), Some(with)) => Ok(Some(Computed {
+            from,
+            with,
+            optional,
+        })),
+        _ => Err(syn::Error::new(
+            attr.span(),
+            "`computed` requires both `from` and `with`",
+        )),
+    }
 }
 
 struct Shared<'a> {
@@ -60,6 +201,8 @@ struct Shared<'a> {
     ty_generics: TypeGenerics<'a>,
     where_clause: Option<&'a WhereClause>,
     trait_path: Path,
+    data_path: Path,
+    set_data_path: Path,
     struct_name: &'a Ident,
 }
 
@@ -75,10 +218,34 @@ struct Shared<'a> {
 /// - be optional (exists only if `MyState::Foo`),
 /// - use default value for initial state,
 /// - be mutated by replacement if exists.
-#[proc_macro_derive(State, attributes(dependency))]
+///
+/// If attributed with `#[dependency(MyState = MyState::Foo, retain)]`, the state will
+/// additionally remember its value while disabled and resume from it when enabled again.
+///
+/// Multiple dependencies can be combined with `all(...)`, `any(...)` and `not(...)`,
+/// e.g. `#[dependency(all(GameState = GameState::Playing, not(Paused = Paused::Yes)))]`.
+/// Multiple `#[dependency]` attributes behave like `all(...)`.
+/// The state will then depend on all of the listed states and exist only if the condition is met.
+///
+/// If attributed with `#[computed(from = (StateA, StateB), with = compute)]`, the state will:
+/// - have the listed dependencies,
+/// - not be mutable through updates,
+/// - be computed by `fn compute(a: &StateA, b: &StateB) -> Option<Self>` from current dependency values,
+/// - be optional, unless additionally attributed with `non_optional`,
+///   in which case the function has to return `Self`.
+///
+/// Optional dependencies are passed to the function as `&Option<T>`.
+#[proc_macro_derive(State, attributes(dependency, computed))]
 pub fn derive_state(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
-    let dependency = parse_sources_attr(&ast).expect("failed to parse dependency");
+    let dependency = match parse_sources_attr(&ast) {
+        Ok(dependency) => dependency,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let computed = match parse_computed_attr(&ast) {
+        Ok(computed) => computed,
+        Err(e) => return e.to_compile_error().into(),
+    };
 
     let generics = ast.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
@@ -89,6 +256,18 @@ pub fn derive_state(input: TokenStream) -> TokenStream {
     let mut trait_path = base_path.clone();
     trait_path.segments.push(format_ident!("State").into());
 
+    let mut data_path = bevy_state_path();
+    data_path.segments.push(format_ident!("components").into());
+    data_path.segments.push(format_ident!("StateData").into());
+
+    let mut set_data_path = bevy_state_path();
+    set_data_path
+        .segments
+        .push(format_ident!("state_set").into());
+    set_data_path
+        .segments
+        .push(format_ident!("StateSetData").into());
+
     let struct_name = &ast.ident;
 
     let shared = Shared {
@@ -96,12 +275,20 @@ pub fn derive_state(input: TokenStream) -> TokenStream {
         ty_generics,
         where_clause,
         trait_path,
+        data_path,
+        set_data_path,
         struct_name,
     };
 
-    let result = match dependency {
-        Some(dependency) => derive_sub_state(shared, dependency),
-        None => derive_root_state(shared),
+    let result = match (dependency, computed) {
+        (Some(_), Some(_)) => syn::Error::new(
+            shared.struct_name.span(),
+            "`dependency` and `computed` attributes can't be used together",
+        )
+        .to_compile_error(),
+        (Some(dependency), None) => derive_sub_state(shared, dependency),
+        (None, Some(computed)) => derive_computed_state(shared, computed),
+        (None, None) => derive_root_state(shared),
     };
 
     result.into()
@@ -113,6 +300,8 @@ fn derive_root_state(shared: Shared) -> proc_macro2::TokenStream {
         ty_generics,
         where_clause,
         trait_path,
+        data_path,
+        set_data_path,
         struct_name,
     } = shared;
     quote! {
@@ -122,8 +311,8 @@ fn derive_root_state(shared: Shared) -> proc_macro2::TokenStream {
             type Repr = Self;
 
             fn update<'a>(
-                state: &mut StateData<Self>,
-                _: StateSetData<'_, Self::Dependencies>,
+                state: &mut #data_path<Self>,
+                _: #set_data_path<'_, Self::Dependencies>,
             ) -> Self::Repr {
                 state.update_mut().take().unwrap()
             }
@@ -137,29 +326,192 @@ fn derive_sub_state(shared: Shared, dependency: Dependency) -> proc_macro2::Toke
         ty_generics,
         where_clause,
         trait_path,
+        data_path,
+        set_data_path,
         struct_name,
     } = shared;
-    let Dependency {
-        ty: dependency_ty,
-        value: dependency_value,
-    } = dependency;
+    let Dependency { condition, retain } = dependency;
+    let mut types = vec![];
+    condition.collect_types(&mut types);
+    let enabled = condition.to_expr(&types);
+    let variables = (0..types.len()).map(|i| format_ident!("dependency_{}", i));
+    let (dependencies_ty, bindings) = match types.as_slice() {
+        [ty] => (quote! { #ty }, quote! { #(#variables)* }),
+        _ => (quote! { (#(#types),*) }, quote! { (#(#variables),*) }),
+    };
+    if retain {
+        let mut update_path = bevy_state_path();
+        update_path.segments.push(format_ident!("backends").into());
+        update_path.segments.push(format_ident!("retained").into());
+        update_path
+            .segments
+            .push(format_ident!("RetainedUpdate").into());
+        return quote! {
+            impl #impl_generics #trait_path for #struct_name #ty_generics #where_clause {
+                type Dependencies = #dependencies_ty;
+                type Update = #update_path<Self>;
+                type Repr = Option<Self>;
+
+                fn update<'a>(
+                    state: &mut #data_path<Self>,
+                    dependencies: #set_data_path<'_, Self::Dependencies>,
+                ) -> Self::Repr {
+                    let #bindings = dependencies;
+                    let enabled = #enabled;
+                    state.apply_retained_update(enabled)
+                }
+            }
+        };
+    }
     quote! {
         impl #impl_generics #trait_path for #struct_name #ty_generics #where_clause {
-            type Dependencies = #dependency_ty;
+            type Dependencies = #dependencies_ty;
             type Update = Option<Self>;
             type Repr = Option<Self>;
 
             fn update<'a>(
-                state: &mut StateData<Self>,
-                dependencies: StateSetData<'_, Self::Dependencies>,
+                state: &mut #data_path<Self>,
+                dependencies: #set_data_path<'_, Self::Dependencies>,
             ) -> Self::Repr {
-                let manual = dependencies;
-                match (manual.current(), state.update_mut().take()) {
-                    (#dependency_value, None) => Some(Self::default()),
-                    (#dependency_value, Some(next)) => Some(next),
-                    _ => None,
+                let #bindings = dependencies;
+                let next = state.update_mut().take();
+                if #enabled {
+                    Some(next.unwrap_or_default())
+                } else {
+                    None
                 }
             }
         }
     }
 }
+
+fn derive_computed_state(shared: Shared, computed: Computed) -> proc_macro2::TokenStream {
+    let Shared {
+        impl_generics,
+        ty_generics,
+        where_clause,
+        trait_path,
+        data_path,
+        set_data_path,
+        struct_name,
+    } = shared;
+    let Computed {
+        from,
+        with,
+        optional,
+    } = computed;
+    let variables = (0..from.len())
+        .map(|i| format_ident!("dependency_{}", i))
+        .collect::<Vec<_>>();
+    let (dependencies_ty, bindings) = match from.as_slice() {
+        [ty] => (quote! { #ty }, quote! { #(#variables)* }),
+        _ => (quote! { (#(#from),*) }, quote! { (#(#variables),*) }),
+    };
+    let repr = if optional {
+        quote! { Option<Self> }
+    } else {
+        quote! { Self }
+    };
+    quote! {
+        impl #impl_generics #trait_path for #struct_name #ty_generics #where_clause {
+            type Dependencies = #dependencies_ty;
+            type Update = ();
+            type Repr = #repr;
+
+            fn update<'a>(
+                _state: &mut #data_path<Self>,
+                dependencies: #set_data_path<'_, Self::Dependencies>,
+            ) -> Self::Repr {
+                let #bindings = dependencies;
+                #with(#(#variables.current()),*)
+            }
+        }
+    }
+}
+
+/// Returns whether the type is attributed with `#[variants(clamp)]`.
+fn parse_variants_attr(ast: &DeriveInput) -> Result<bool> {
+    let mut clamp = false;
+    for attr in ast.attrs.iter().filter(|a| a.path().is_ident("variants")) {
+        attr.parse_nested_meta(|nested| {
+            if nested.path.is_ident("clamp") {
+                clamp = true;
+                Ok(())
+            } else if nested.path.is_ident("wrap") {
+                clamp = false;
+                Ok(())
+            } else {
+                Err(nested.error("expected `clamp` or `wrap`"))
+            }
+        })?;
+    }
+    Ok(clamp)
+}
+
+/// Macro for deriving `Variants` trait.
+///
+/// Only non-empty enums with unit variants are supported.
+/// Variants are listed in declaration order.
+///
+/// By default shifting past the ends wraps around,
+/// if attributed with `#[variants(clamp)]` shifting stops at the ends instead.
+#[proc_macro_derive(Variants, attributes(variants))]
+pub fn derive_variants(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    let clamp = match parse_variants_attr(&ast) {
+        Ok(clamp) => clamp,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let Data::Enum(data) = &ast.data else {
+        return syn::Error::new(ast.span(), "`Variants` can only be derived for enums")
+            .to_compile_error()
+            .into();
+    };
+    if let Some(variant) = data
+        .variants
+        .iter()
+        .find(|variant| !matches!(variant.fields, Fields::Unit))
+    {
+        return syn::Error::new(variant.span(), "`Variants` requires unit variants")
+            .to_compile_error()
+            .into();
+    }
+    if data.variants.is_empty() {
+        return syn::Error::new(ast.span(), "`Variants` requires at least one variant")
+            .to_compile_error()
+            .into();
+    }
+    let variants = data.variants.iter().map(|variant| &variant.ident);
+
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let mut base_path = bevy_state_path();
+    base_path.segments.push(format_ident!("backends").into());
+    base_path.segments.push(format_ident!("shift").into());
+
+    let mut trait_path = base_path.clone();
+    trait_path.segments.push(format_ident!("Variants").into());
+
+    let mut edge_path = base_path;
+    edge_path.segments.push(format_ident!("ShiftEdge").into());
+
+    let edge = clamp.then(|| {
+        quote! {
+            const EDGE: #edge_path = #edge_path::Clamp;
+        }
+    });
+
+    let enum_name = &ast.ident;
+
+    quote! {
+        impl #impl_generics #trait_path for #enum_name #ty_generics #where_clause {
+            #edge
+
+            fn variants() -> &'static [Self] {
+                &[#(Self::#variants),*]
+            }
+        }
+    }
+    .into()
+}
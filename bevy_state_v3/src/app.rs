@@ -6,6 +6,8 @@ use crate::system_set::StateUpdates;
 
 /// Plugin state registers:
 /// - [`StateUpdates`] schedule, which uses state's update data and dependencies to set the new value of a state,
+/// - in debug builds, [`audit_state_entities`](crate::validation::audit_state_entities) system,
+///   which reports state entities with missing dependencies.
 ///
 /// State updates and transitions run in the main schedule "inbetween" frames, meanwhile
 /// in startup only the transition schedule is executed to trigger initial transition events.
@@ -17,5 +19,7 @@ impl Plugin for StatePlugin {
         let mut schedule = app.world_mut().resource_mut::<MainScheduleOrder>();
         schedule.insert_startup_before(PreStartup, StateUpdates);
         schedule.insert_after(PreUpdate, StateUpdates);
+        #[cfg(debug_assertions)]
+        app.add_systems(bevy_app::Last, crate::validation::audit_state_entities);
     }
 }
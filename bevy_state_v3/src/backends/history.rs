@@ -0,0 +1,539 @@
+//! State update backend with undo and redo.
+//!
+//! States using [`HistoryUpdate`] record a bounded history of their values.
+//! Undoing and redoing moves through that history and is applied like any other update,
+//! so the usual transitions like [`OnExit`](crate::transitions::OnExit) and [`OnEnter`](crate::transitions::OnEnter) are triggered.
+//! ```rs
+//! #[derive(Debug, Clone, PartialEq)]
+//! enum Tool {
+//!     Brush,
+//!     Eraser,
+//!     Fill,
+//! }
+//!
+//! impl State for Tool {
+//!     type Dependencies = ();
+//!     type Update = HistoryUpdate<Self>;
+//!     type Repr = Self;
+//!
+//!     fn update(state: &mut StateData<Self>, _: StateSetData<'_, Self::Dependencies>) -> Self::Repr {
+//!         state.apply_history_op()
+//!     }
+//! }
+//! ```
+//! New values are requested through [`CoreStatesExt::update_state`](crate::commands::CoreStatesExt::update_state)
+//! or [`HistoryStatesExt::set_state`], which also supports optional states,
+//! while undo and redo through [`HistoryStatesExt`] methods.
+//!
+//! Only values set through history operations are recorded.
+//! Values computed differently by [`State::update`], like values forced by dependencies, are not recorded.
+
+use std::collections::VecDeque;
+
+use bevy_ecs::prelude::{Command, Commands, Entity, World};
+
+use crate::{
+    commands::ModifyStateDataCommand,
+    components::StateData,
+    error::warn_error,
+    state::{State, StateRepr, StateUpdate},
+};
+
+/// Default number of values kept in the history.
+pub const DEFAULT_HISTORY_CAPACITY: usize = 64;
+
+/// States which use the [`HistoryUpdate`] backend.
+pub trait HistoryState: State<Update = HistoryUpdate<Self>> {}
+
+impl<S: State<Update = HistoryUpdate<S>>> HistoryState for S {}
+
+/// Operation on the state history.
+#[derive(Debug, Clone)]
+pub enum HistoryOp<S: State> {
+    /// Sets a new value, discarding values that could be redone.
+    Set(S::Repr),
+    /// Returns to the previous value in history.
+    Undo,
+    /// Returns to the next value in history.
+    Redo,
+}
+
+/// Update data structure for states with undo and redo.
+#[derive(Debug, Clone)]
+pub struct HistoryUpdate<S: State> {
+    /// Values that can be undone, from oldest to newest.
+    past: VecDeque<S::Repr>,
+    /// Values that can be redone, the next value is last.
+    future: Vec<S::Repr>,
+    /// Pending history operation.
+    op: Option<HistoryOp<S>>,
+    /// Maximum number of values kept in the history.
+    capacity: usize,
+}
+
+impl<S: State> Default for HistoryUpdate<S> {
+    fn default() -> Self {
+        Self {
+            past: Default::default(),
+            future: Default::default(),
+            op: None,
+            capacity: DEFAULT_HISTORY_CAPACITY,
+        }
+    }
+}
+
+impl<S: State<Repr = S>> From<S> for HistoryUpdate<S> {
+    fn from(value: S) -> Self {
+        Self {
+            op: Some(HistoryOp::Set(value)),
+            ..Default::default()
+        }
+    }
+}
+
+impl<S: State> StateUpdate for HistoryUpdate<S> {
+    fn should_update(&self) -> bool {
+        self.op.is_some()
+    }
+
+    fn post_update(&mut self) {
+        self.op.take();
+    }
+
+    fn merge(&mut self, requested: Self) {
+        self.op = requested.op;
+    }
+
+    fn reset(&mut self) {
+        self.past.clear();
+        self.future.clear();
+        self.op = None;
+    }
+}
+
+impl<S: State> HistoryUpdate<S> {
+    /// Returns the pending history operation.
+    pub fn op(&self) -> Option<&HistoryOp<S>> {
+        self.op.as_ref()
+    }
+
+    /// Returns the maximum number of values kept in the history.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Sets the maximum number of values kept in the history.
+    /// Oldest values are discarded if the history is too long.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.past.len() > capacity {
+            self.past.pop_front();
+        }
+        if self.future.len() > capacity {
+            self.future.drain(..self.future.len() - capacity);
+        }
+    }
+
+    /// Records a value that can be undone.
+    fn push_past(&mut self, value: S::Repr) {
+        self.past.push_back(value);
+        if self.past.len() > self.capacity {
+            self.past.pop_front();
+        }
+    }
+}
+
+impl<S: HistoryState> StateData<S> {
+    /// Applies the pending history operation and returns the new value.
+    /// Meant to be called from [`State::update`].
+    ///
+    /// If no operation is pending, which happens when a dependency was updated,
+    /// or there is nothing to undo or redo, the current value is kept.
+    /// Setting the current value again is not recorded in the history.
+    /// If [`State::update`] returns a different value than this, that value is not recorded either.
+    pub fn apply_history_op(&mut self) -> S::Repr {
+        let Some(op) = self.update.op.take() else {
+            return self.current.clone();
+        };
+        match op {
+            HistoryOp::Set(value) => {
+                if value != self.current {
+                    self.update.future.clear();
+                    self.update.push_past(self.current.clone());
+                }
+                value
+            }
+            HistoryOp::Undo => match self.update.past.pop_back() {
+                Some(value) => {
+                    self.update.future.push(self.current.clone());
+                    value
+                }
+                None => self.current.clone(),
+            },
+            HistoryOp::Redo => match self.update.future.pop() {
+                Some(value) => {
+                    self.update.push_past(self.current.clone());
+                    value
+                }
+                None => self.current.clone(),
+            },
+        }
+    }
+
+    /// Returns values that can be undone, from oldest to newest.
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &S::Repr> {
+        self.update.past.iter()
+    }
+
+    /// Returns values that can be redone, from next to last.
+    pub fn redo_history(&self) -> impl DoubleEndedIterator<Item = &S::Repr> {
+        self.update.future.iter().rev()
+    }
+
+    /// Returns whether there is a value to undo to.
+    pub fn can_undo(&self) -> bool {
+        !self.update.past.is_empty()
+    }
+
+    /// Returns whether there is a value to redo to.
+    pub fn can_redo(&self) -> bool {
+        !self.update.future.is_empty()
+    }
+}
+
+/// Creates a command which schedules a history operation.
+fn history_op_command<S: HistoryState>(
+    local: Option<Entity>,
+    op: HistoryOp<S>,
+) -> ModifyStateDataCommand<S, impl FnOnce(&mut StateData<S>) + Send + 'static> {
+    ModifyStateDataCommand::new(local, move |state: &mut StateData<S>| {
+        state.update.op = Some(op);
+    })
+}
+
+/// Methods for moving through state history.
+/// Only one operation can be applied per state update, a new request overwrites the pending one.
+///
+/// Depending on which medium this is called on, those methods will have:
+/// - immediate effect: [`World`], [`SubApp`](bevy_app::SubApp) and [`App`](bevy_app::App),
+/// - deferred effect: [`Commands`].
+pub trait HistoryStatesExt {
+    /// Sets a new value, discarding values that could be redone.
+    /// Unlike [`CoreStatesExt::update_state`](crate::commands::CoreStatesExt::update_state),
+    /// this also works for optional states.
+    fn set_state<R: StateRepr>(&mut self, local: Option<Entity>, value: R) -> &mut Self
+    where
+        R::State: HistoryState;
+
+    /// Returns the state to the previous value in history.
+    fn undo_state<S: HistoryState>(&mut self, local: Option<Entity>) -> &mut Self;
+
+    /// Returns the state to the next value in history.
+    fn redo_state<S: HistoryState>(&mut self, local: Option<Entity>) -> &mut Self;
+}
+
+impl HistoryStatesExt for Commands<'_, '_> {
+    fn set_state<R: StateRepr>(&mut self, local: Option<Entity>, value: R) -> &mut Self
+    where
+        R::State: HistoryState,
+    {
+        self.queue(history_op_command(local, HistoryOp::<R::State>::Set(value)));
+        self
+    }
+
+    fn undo_state<S: HistoryState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.queue(history_op_command(local, HistoryOp::<S>::Undo));
+        self
+    }
+
+    fn redo_state<S: HistoryState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.queue(history_op_command(local, HistoryOp::<S>::Redo));
+        self
+    }
+}
+
+impl HistoryStatesExt for World {
+    fn set_state<R: StateRepr>(&mut self, local: Option<Entity>, value: R) -> &mut Self
+    where
+        R::State: HistoryState,
+    {
+        history_op_command(local, HistoryOp::<R::State>::Set(value))
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+
+    fn undo_state<S: HistoryState>(&mut self, local: Option<Entity>) -> &mut Self {
+        history_op_command(local, HistoryOp::<S>::Undo)
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+
+    fn redo_state<S: HistoryState>(&mut self, local: Option<Entity>) -> &mut Self {
+        history_op_command(local, HistoryOp::<S>::Redo)
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl HistoryStatesExt for bevy_app::SubApp {
+    fn set_state<R: StateRepr>(&mut self, local: Option<Entity>, value: R) -> &mut Self
+    where
+        R::State: HistoryState,
+    {
+        self.world_mut().set_state(local, value);
+        self
+    }
+
+    fn undo_state<S: HistoryState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.world_mut().undo_state::<S>(local);
+        self
+    }
+
+    fn redo_state<S: HistoryState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.world_mut().redo_state::<S>(local);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl HistoryStatesExt for bevy_app::App {
+    fn set_state<R: StateRepr>(&mut self, local: Option<Entity>, value: R) -> &mut Self
+    where
+        R::State: HistoryState,
+    {
+        self.main_mut().set_state(local, value);
+        self
+    }
+
+    fn undo_state<S: HistoryState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.main_mut().undo_state::<S>(local);
+        self
+    }
+
+    fn redo_state<S: HistoryState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.main_mut().redo_state::<S>(local);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{schedule::Schedules, world::World};
+    use bevy_state_macros::State;
+
+    use super::{HistoryStatesExt, HistoryUpdate};
+    use crate::{
+        self as bevy_state_v3, commands::CoreStatesExt, components::StateData, config::StateConfig,
+        state::State, state_set::StateSetData, system_set::StateUpdates,
+    };
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Tool {
+        Brush,
+        Eraser,
+        Fill,
+        Select,
+    }
+
+    impl State for Tool {
+        type Dependencies = ();
+        type Update = HistoryUpdate<Self>;
+        type Repr = Self;
+
+        fn update(
+            state: &mut StateData<Self>,
+            _: StateSetData<'_, Self::Dependencies>,
+        ) -> Self::Repr {
+            state.apply_history_op()
+        }
+    }
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Lock {
+        Unlocked,
+        Locked,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Brush {
+        Round,
+        Square,
+    }
+
+    impl State for Brush {
+        type Dependencies = Lock;
+        type Update = HistoryUpdate<Self>;
+        type Repr = Self;
+
+        fn update(
+            state: &mut StateData<Self>,
+            _: StateSetData<'_, Self::Dependencies>,
+        ) -> Self::Repr {
+            state.apply_history_op()
+        }
+
+        fn guard(
+            _state: &StateData<Self>,
+            _next: &Self::Repr,
+            dependencies: StateSetData<'_, Self::Dependencies>,
+        ) -> bool {
+            let lock = dependencies;
+            lock.current() == &Lock::Unlocked
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Selection {
+        Layer,
+        Mask,
+    }
+
+    impl State for Selection {
+        type Dependencies = ();
+        type Update = HistoryUpdate<Self>;
+        type Repr = Option<Self>;
+
+        fn update(
+            state: &mut StateData<Self>,
+            _: StateSetData<'_, Self::Dependencies>,
+        ) -> Self::Repr {
+            state.apply_history_op()
+        }
+    }
+
+    fn current(world: &mut World) -> Tool {
+        world
+            .query::<&StateData<Tool>>()
+            .single(world)
+            .unwrap()
+            .current()
+            .clone()
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Tool>(StateConfig::empty());
+        world.init_state(None, Tool::Brush);
+
+        world.update_state(None, Tool::Eraser);
+        world.run_schedule(StateUpdates);
+        world.update_state(None, Tool::Fill);
+        world.run_schedule(StateUpdates);
+
+        world.undo_state::<Tool>(None);
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), Tool::Eraser);
+        world.undo_state::<Tool>(None);
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), Tool::Brush);
+        world.undo_state::<Tool>(None);
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), Tool::Brush);
+
+        world.redo_state::<Tool>(None);
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), Tool::Eraser);
+
+        world.update_state(None, Tool::Select);
+        world.run_schedule(StateUpdates);
+        world.redo_state::<Tool>(None);
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), Tool::Select);
+
+        let state = world.query::<&StateData<Tool>>().single(&world).unwrap();
+        assert!(state.history().eq([Tool::Brush, Tool::Eraser].iter()));
+        assert!(!state.can_redo());
+    }
+
+    #[test]
+    fn reset_keeps_capacity() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Tool>(StateConfig::empty());
+        world.init_state(None, Tool::Brush);
+        let mut state = world
+            .query::<&mut StateData<Tool>>()
+            .single_mut(&mut world)
+            .unwrap();
+        state.update_mut().set_capacity(2);
+
+        world.update_state(None, Tool::Eraser);
+        world.run_schedule(StateUpdates);
+        world.reset_state::<Tool>(None);
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), Tool::Brush);
+
+        let state = world.query::<&StateData<Tool>>().single(&world).unwrap();
+        assert!(!state.can_undo());
+        assert_eq!(state.update().capacity(), 2);
+    }
+
+    #[test]
+    fn rejected_undo() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Lock>(StateConfig::empty());
+        world.register_state::<Brush>(StateConfig::empty());
+        world.init_state(None, Lock::Unlocked);
+        world.init_state(None, Brush::Round);
+        world.update_state(None, Brush::Square);
+        world.run_schedule(StateUpdates);
+        world.update_state(None, Lock::Locked);
+        world.run_schedule(StateUpdates);
+
+        // Rejected undo leaves the history untouched.
+        world.undo_state::<Brush>(None);
+        world.run_schedule(StateUpdates);
+        let state = world.query::<&StateData<Brush>>().single(&world).unwrap();
+        assert_eq!(state.current(), &Brush::Square);
+        assert!(state.history().eq([Brush::Round].iter()));
+        assert!(!state.can_redo());
+
+        world.update_state(None, Lock::Unlocked);
+        world.run_schedule(StateUpdates);
+        world.undo_state::<Brush>(None);
+        world.run_schedule(StateUpdates);
+        let state = world.query::<&StateData<Brush>>().single(&world).unwrap();
+        assert_eq!(state.current(), &Brush::Round);
+    }
+
+    #[test]
+    fn optional_history() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Selection>(StateConfig::empty());
+        world.init_state(None, None::<Selection>);
+
+        world.set_state(None, Some(Selection::Layer));
+        world.run_schedule(StateUpdates);
+        world.set_state(None, Some(Selection::Mask));
+        world.run_schedule(StateUpdates);
+        world.set_state(None, None::<Selection>);
+        world.run_schedule(StateUpdates);
+
+        let state = world
+            .query::<&StateData<Selection>>()
+            .single(&world)
+            .unwrap();
+        assert_eq!(state.current(), &None);
+        assert!(
+            state
+                .history()
+                .eq([None, Some(Selection::Layer), Some(Selection::Mask)].iter())
+        );
+
+        world.undo_state::<Selection>(None);
+        world.run_schedule(StateUpdates);
+        let state = world
+            .query::<&StateData<Selection>>()
+            .single(&world)
+            .unwrap();
+        assert_eq!(state.current(), &Some(Selection::Mask));
+    }
+}
@@ -0,0 +1,11 @@
+//! Built-in state update backends.
+//!
+//! Backends are [`StateUpdate`](crate::state::StateUpdate) implementations for mechanics
+//! that go beyond replacing the state with a new value.
+//! Each backend comes with commands for requesting updates and helpers for implementing [`State::update`](crate::state::State::update).
+
+pub mod history;
+pub mod queue;
+pub mod retained;
+pub mod shift;
+pub mod stack;
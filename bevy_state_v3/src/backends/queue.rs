@@ -0,0 +1,258 @@
+//! Queued state update backend.
+//!
+//! States using [`QueueUpdate`] keep every requested value, instead of overwriting
+//! the pending request when multiple updates are requested in the same frame.
+//! Each [`StateUpdates`](crate::system_set::StateUpdates) run applies one value from the queue,
+//! so every intermediate value triggers its own transitions.
+//! This makes scripted sequences, like cutscenes, deterministic.
+//! ```rs
+//! #[derive(Debug, Clone, PartialEq)]
+//! enum Cutscene {
+//!     Intro,
+//!     Dialogue,
+//!     Outro,
+//! }
+//!
+//! impl State for Cutscene {
+//!     type Dependencies = ();
+//!     type Update = QueueUpdate<Self>;
+//!     type Repr = Self;
+//!
+//!     fn update(state: &mut StateData<Self>, _: StateSetData<'_, Self::Dependencies>) -> Self::Repr {
+//!         state.apply_queued_update()
+//!     }
+//! }
+//! ```
+//! Values are queued through [`CoreStatesExt::update_state`](crate::commands::CoreStatesExt::update_state)
+//! or [`QueueStatesExt::enqueue_state`].
+
+use std::collections::VecDeque;
+
+use bevy_ecs::prelude::{Command, Commands, Entity, World};
+
+use crate::{
+    commands::ModifyStateDataCommand,
+    components::StateData,
+    error::warn_error,
+    state::{State, StateRepr, StateUpdate},
+};
+
+/// States which use the [`QueueUpdate`] backend.
+pub trait QueueState: State<Update = QueueUpdate<Self>> {}
+
+impl<S: State<Update = QueueUpdate<S>>> QueueState for S {}
+
+/// Update data structure for states which apply requested values in order.
+#[derive(Debug, Clone)]
+pub struct QueueUpdate<S: State> {
+    /// Requested values, the next one is first.
+    queue: VecDeque<S::Repr>,
+}
+
+impl<S: State> Default for QueueUpdate<S> {
+    fn default() -> Self {
+        Self {
+            queue: Default::default(),
+        }
+    }
+}
+
+impl<S: State<Repr = S>> From<S> for QueueUpdate<S> {
+    fn from(value: S) -> Self {
+        Self {
+            queue: VecDeque::from([value]),
+        }
+    }
+}
+
+impl<S: State> StateUpdate for QueueUpdate<S> {
+    fn should_update(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    fn post_update(&mut self) {
+        self.queue.pop_front();
+    }
+
+    fn merge(&mut self, requested: Self) {
+        self.queue.extend(requested.queue);
+    }
+}
+
+impl<S: State> QueueUpdate<S> {
+    /// Returns the queued values, the next one is first.
+    pub fn queued(&self) -> impl ExactSizeIterator<Item = &S::Repr> {
+        self.queue.iter()
+    }
+}
+
+impl<S: QueueState> StateData<S> {
+    /// Returns the next queued value.
+    /// Meant to be called from [`State::update`].
+    ///
+    /// If the queue is empty, which happens when a dependency was updated,
+    /// the current value is kept.
+    /// The value is removed from the queue after the update.
+    pub fn apply_queued_update(&mut self) -> S::Repr {
+        self.update
+            .queue
+            .front()
+            .cloned()
+            .unwrap_or_else(|| self.current.clone())
+    }
+}
+
+/// Creates a command which modifies the queue.
+fn queue_command<S: QueueState>(
+    local: Option<Entity>,
+    modify: impl FnOnce(&mut VecDeque<S::Repr>) + Send + 'static,
+) -> ModifyStateDataCommand<S, impl FnOnce(&mut StateData<S>) + Send + 'static> {
+    ModifyStateDataCommand::new(local, move |state: &mut StateData<S>| {
+        modify(&mut state.update.queue);
+    })
+}
+
+/// Methods for managing queued states.
+///
+/// Depending on which medium this is called on, those methods will have:
+/// - immediate effect: [`World`], [`SubApp`](bevy_app::SubApp) and [`App`](bevy_app::App),
+/// - deferred effect: [`Commands`].
+pub trait QueueStatesExt {
+    /// Adds a value to the end of the queue.
+    /// Unlike [`CoreStatesExt::update_state`](crate::commands::CoreStatesExt::update_state) this works for optional states too.
+    fn enqueue_state<R: StateRepr>(&mut self, local: Option<Entity>, value: R) -> &mut Self
+    where
+        R::State: QueueState;
+
+    /// Removes all values from the queue.
+    fn clear_state_queue<S: QueueState>(&mut self, local: Option<Entity>) -> &mut Self;
+}
+
+impl QueueStatesExt for Commands<'_, '_> {
+    fn enqueue_state<R: StateRepr>(&mut self, local: Option<Entity>, value: R) -> &mut Self
+    where
+        R::State: QueueState,
+    {
+        self.queue(queue_command::<R::State>(local, move |queue| {
+            queue.push_back(value);
+        }));
+        self
+    }
+
+    fn clear_state_queue<S: QueueState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.queue(queue_command::<S>(local, VecDeque::clear));
+        self
+    }
+}
+
+impl QueueStatesExt for World {
+    fn enqueue_state<R: StateRepr>(&mut self, local: Option<Entity>, value: R) -> &mut Self
+    where
+        R::State: QueueState,
+    {
+        queue_command::<R::State>(local, move |queue| {
+            queue.push_back(value);
+        })
+        .apply(self)
+        .unwrap_or_else(warn_error);
+        self
+    }
+
+    fn clear_state_queue<S: QueueState>(&mut self, local: Option<Entity>) -> &mut Self {
+        queue_command::<S>(local, VecDeque::clear)
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl QueueStatesExt for bevy_app::SubApp {
+    fn enqueue_state<R: StateRepr>(&mut self, local: Option<Entity>, value: R) -> &mut Self
+    where
+        R::State: QueueState,
+    {
+        self.world_mut().enqueue_state(local, value);
+        self
+    }
+
+    fn clear_state_queue<S: QueueState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.world_mut().clear_state_queue::<S>(local);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl QueueStatesExt for bevy_app::App {
+    fn enqueue_state<R: StateRepr>(&mut self, local: Option<Entity>, value: R) -> &mut Self
+    where
+        R::State: QueueState,
+    {
+        self.main_mut().enqueue_state(local, value);
+        self
+    }
+
+    fn clear_state_queue<S: QueueState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.main_mut().clear_state_queue::<S>(local);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{schedule::Schedules, world::World};
+
+    use super::QueueUpdate;
+    use crate::{
+        commands::CoreStatesExt, components::StateData, config::StateConfig, state::State,
+        state_set::StateSetData, system_set::StateUpdates,
+    };
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Cutscene {
+        Intro,
+        Dialogue,
+        Outro,
+    }
+
+    impl State for Cutscene {
+        type Dependencies = ();
+        type Update = QueueUpdate<Self>;
+        type Repr = Self;
+
+        fn update(
+            state: &mut StateData<Self>,
+            _: StateSetData<'_, Self::Dependencies>,
+        ) -> Self::Repr {
+            state.apply_queued_update()
+        }
+    }
+
+    #[test]
+    fn queued_updates_apply_in_order() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Cutscene>(StateConfig::empty());
+        world.init_state(None, Cutscene::Outro);
+        world.update_state(None, Cutscene::Intro);
+        world.update_state(None, Cutscene::Dialogue);
+        world.update_state(None, Cutscene::Outro);
+
+        for expected in [Cutscene::Intro, Cutscene::Dialogue, Cutscene::Outro] {
+            world.run_schedule(StateUpdates);
+            let state = world
+                .query::<&StateData<Cutscene>>()
+                .single(&world)
+                .unwrap();
+            assert!(state.is_updated());
+            assert_eq!(state.current(), &expected);
+        }
+
+        world.run_schedule(StateUpdates);
+        let state = world
+            .query::<&StateData<Cutscene>>()
+            .single(&world)
+            .unwrap();
+        assert!(!state.is_updated());
+    }
+}
@@ -0,0 +1,235 @@
+//! Retained substate update backend.
+//!
+//! Substates using [`RetainedUpdate`] remember their last value when the parent state disables them,
+//! and resume from it once the parent enables them again, instead of resetting to [`Default`].
+//!
+//! The [`State`](bevy_state_macros::State) derive macro supports this backend through the `retain` option:
+//! ```rs
+//! #[derive(State, Default, Debug, Clone, PartialEq)]
+//! #[dependency(MyState = MyState::Alice, retain)]
+//! enum MySubstate {
+//!     #[default]
+//!     Foo,
+//!     Bar,
+//! }
+//! ```
+//! Updates are requested the same way as for other substates, through [`CoreStatesExt::update_state`](crate::commands::CoreStatesExt::update_state).
+//! The retained value can be discarded with [`RetainedStatesExt::forget_state`].
+
+use bevy_ecs::prelude::{Command, Commands, Entity, World};
+
+use crate::{
+    commands::ModifyStateDataCommand,
+    components::StateData,
+    error::warn_error,
+    state::{State, StateUpdate},
+};
+
+/// States which use the [`RetainedUpdate`] backend.
+pub trait RetainedState:
+    State<Repr = Option<Self>, Update = RetainedUpdate<Self>> + Default
+{
+}
+
+impl<S: State<Repr = Option<S>, Update = RetainedUpdate<S>> + Default> RetainedState for S {}
+
+/// Update data structure for substates which retain their value while disabled.
+#[derive(Debug, Clone)]
+pub struct RetainedUpdate<S: State> {
+    /// Requested value for the next update.
+    next: Option<S>,
+    /// Value the state had when it was last disabled.
+    retained: Option<S>,
+    /// Whether the current value should be discarded instead of retained when the state gets disabled.
+    forget: bool,
+}
+
+impl<S: State> Default for RetainedUpdate<S> {
+    fn default() -> Self {
+        Self {
+            next: None,
+            retained: None,
+            forget: false,
+        }
+    }
+}
+
+impl<S: State> From<S> for RetainedUpdate<S> {
+    fn from(value: S) -> Self {
+        Self {
+            next: Some(value),
+            ..Default::default()
+        }
+    }
+}
+
+impl<S: State> StateUpdate for RetainedUpdate<S> {
+    fn should_update(&self) -> bool {
+        self.next.is_some()
+    }
+
+    fn post_update(&mut self) {
+        self.next.take();
+    }
+
+    fn merge(&mut self, requested: Self) {
+        self.next = requested.next;
+    }
+}
+
+impl<S: State> RetainedUpdate<S> {
+    /// Returns the requested value for the next update.
+    pub fn next(&self) -> Option<&S> {
+        self.next.as_ref()
+    }
+
+    /// Returns the value the state will resume from when it gets enabled.
+    pub fn retained(&self) -> Option<&S> {
+        self.retained.as_ref()
+    }
+}
+
+impl<S: RetainedState> StateData<S> {
+    /// Computes the next value of a retained substate.
+    /// Meant to be called from [`State::update`] with whether the parent states allow this state to exist.
+    ///
+    /// When enabled, the state takes the requested value, keeps its current value,
+    /// resumes from the retained value or falls back to [`Default`], in that order.
+    /// When disabled, the current value is retained and requests are discarded.
+    pub fn apply_retained_update(&mut self, enabled: bool) -> Option<S> {
+        let next = self.update.next.take();
+        if enabled {
+            let value = next
+                .or_else(|| self.current.clone())
+                .or_else(|| self.update.retained.take())
+                .unwrap_or_default();
+            Some(value)
+        } else {
+            if !self.update.forget {
+                // Keep the previously retained value if this state was already disabled.
+                self.update.retained = self.current.clone().or_else(|| self.update.retained.take());
+            }
+            self.update.forget = false;
+            None
+        }
+    }
+}
+
+/// Creates a command which discards the retained value.
+fn forget_command<S: RetainedState>(
+    local: Option<Entity>,
+) -> ModifyStateDataCommand<S, impl FnOnce(&mut StateData<S>) + Send + 'static> {
+    ModifyStateDataCommand::new(local, |state: &mut StateData<S>| {
+        state.update.retained = None;
+        // An enabled state will only be retained once it's disabled,
+        // so we skip retaining it that one time.
+        state.update.forget = state.current.is_some();
+    })
+}
+
+/// Methods for managing retained substates.
+///
+/// Depending on which medium this is called on, those methods will have:
+/// - immediate effect: [`World`], [`SubApp`](bevy_app::SubApp) and [`App`](bevy_app::App),
+/// - deferred effect: [`Commands`].
+pub trait RetainedStatesExt {
+    /// Discards the retained value, so the state starts from [`Default`] when it gets enabled next time.
+    /// If the state is currently enabled, the current value will not be retained when it gets disabled.
+    fn forget_state<S: RetainedState>(&mut self, local: Option<Entity>) -> &mut Self;
+}
+
+impl RetainedStatesExt for Commands<'_, '_> {
+    fn forget_state<S: RetainedState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.queue(forget_command::<S>(local));
+        self
+    }
+}
+
+impl RetainedStatesExt for World {
+    fn forget_state<S: RetainedState>(&mut self, local: Option<Entity>) -> &mut Self {
+        forget_command::<S>(local)
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl RetainedStatesExt for bevy_app::SubApp {
+    fn forget_state<S: RetainedState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.world_mut().forget_state::<S>(local);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl RetainedStatesExt for bevy_app::App {
+    fn forget_state<S: RetainedState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.main_mut().forget_state::<S>(local);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{schedule::Schedules, world::World};
+    use bevy_state_macros::State;
+
+    use super::RetainedStatesExt;
+    use crate::{
+        self as bevy_state_v3, commands::CoreStatesExt, components::StateData, config::StateConfig,
+        system_set::StateUpdates,
+    };
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Parent {
+        Enabled,
+        Disabled,
+    }
+
+    #[derive(State, Default, Clone, Debug, PartialEq)]
+    #[dependency(Parent = Parent::Enabled, retain)]
+    enum Child {
+        #[default]
+        X,
+        Y,
+    }
+
+    fn current(world: &mut World) -> Option<Child> {
+        world
+            .query::<&StateData<Child>>()
+            .single(world)
+            .unwrap()
+            .current()
+            .clone()
+    }
+
+    #[test]
+    fn retain_and_forget() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Parent>(StateConfig::empty());
+        world.register_state::<Child>(StateConfig::empty());
+        world.init_state(None, Parent::Enabled);
+        world.init_state(None, Some(Child::X));
+
+        world.update_state(None, Child::Y);
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), Some(Child::Y));
+
+        world.update_state(None, Parent::Disabled);
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), None);
+
+        world.update_state(None, Parent::Enabled);
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), Some(Child::Y));
+
+        world.forget_state::<Child>(None);
+        world.update_state(None, Parent::Disabled);
+        world.run_schedule(StateUpdates);
+        world.update_state(None, Parent::Enabled);
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), Some(Child::X));
+    }
+}
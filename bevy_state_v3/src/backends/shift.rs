@@ -0,0 +1,359 @@
+//! Ring-shifting state update backend.
+//!
+//! States using [`ShiftUpdate`] move through an ordered list of values,
+//! one step forward or backward at a time.
+//! This is useful for menus, weapon wheels, tutorials and similar sequences.
+//!
+//! The list of values is provided by the [`Variants`] trait,
+//! which can be derived for enums with only unit variants:
+//! ```rs
+//! #[derive(Variants, Debug, Clone, PartialEq)]
+//! #[variants(clamp)]
+//! enum Tutorial {
+//!     Movement,
+//!     Combat,
+//!     Inventory,
+//! }
+//!
+//! impl State for Tutorial {
+//!     type Dependencies = ();
+//!     type Update = ShiftUpdate<Self>;
+//!     type Repr = Self;
+//!
+//!     fn update(state: &mut StateData<Self>, _: StateSetData<'_, Self::Dependencies>) -> Self::Repr {
+//!         state.apply_shift_op()
+//!     }
+//! }
+//! ```
+//! The state is then shifted through [`ShiftStatesExt`] methods.
+
+use core::marker::PhantomData;
+
+use bevy_ecs::prelude::{Command, Commands, Entity, World};
+
+use crate::{
+    commands::ModifyStateDataCommand,
+    components::StateData,
+    error::warn_error,
+    state::{State, StateUpdate},
+};
+
+/// Behavior of shifting past the first or last variant.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ShiftEdge {
+    /// Shifting past an end continues from the other end.
+    #[default]
+    Wrap,
+    /// Shifting past an end stays at that end.
+    Clamp,
+}
+
+/// Trait for types that define all their variants in order.
+///
+/// Can be derived for enums with only unit variants,
+/// the `#[variants(clamp)]` attribute changes the [`Variants::EDGE`] behavior.
+pub trait Variants: Sized + 'static {
+    /// Behavior of shifting past the first or last variant.
+    const EDGE: ShiftEdge = ShiftEdge::Wrap;
+
+    /// Returns all variants of a type in order.
+    /// Can contain duplicates, but cannot be empty.
+    fn variants() -> &'static [Self];
+}
+
+/// States which use the [`ShiftUpdate`] backend.
+pub trait ShiftState: State<Repr = Self, Update = ShiftUpdate<Self>> + Variants {}
+
+impl<S: State<Repr = S, Update = ShiftUpdate<S>> + Variants> ShiftState for S {}
+
+/// Operation on the shifting state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShiftOp {
+    /// Moves to the next variant.
+    Advance,
+    /// Moves to the previous variant.
+    Retreat,
+}
+
+/// Data structure for updating shifting states.
+#[derive(Debug, Clone)]
+pub struct ShiftUpdate<S: State> {
+    /// Position of the current state in [`Variants::variants`].
+    ptr: usize,
+    /// Pending shift operation.
+    op: Option<ShiftOp>,
+    _state: PhantomData<S>,
+}
+
+impl<S: State> Default for ShiftUpdate<S> {
+    fn default() -> Self {
+        Self {
+            ptr: 0,
+            op: Default::default(),
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<S: State> StateUpdate for ShiftUpdate<S> {
+    fn should_update(&self) -> bool {
+        self.op.is_some()
+    }
+
+    fn post_update(&mut self) {
+        self.op.take();
+    }
+}
+
+impl<S: State> ShiftUpdate<S> {
+    /// Returns the pending shift operation.
+    pub fn op(&self) -> Option<ShiftOp> {
+        self.op
+    }
+}
+
+impl<S: ShiftState> StateData<S> {
+    /// Applies the pending shift operation and returns the new value.
+    /// Meant to be called from [`State::update`].
+    ///
+    /// If no operation is pending, which happens when a dependency was updated,
+    /// the current value is kept.
+    pub fn apply_shift_op(&mut self) -> S {
+        let variants = S::variants();
+        let len = variants.len();
+        // Find the current value if it wasn't reached by shifting,
+        // e.g. initial value or manual modification.
+        if variants.get(self.update.ptr) != Some(&self.current) {
+            self.update.ptr = variants
+                .iter()
+                .position(|variant| variant == &self.current)
+                .unwrap_or(0);
+        }
+        let Some(op) = self.update.op.take() else {
+            return self.current.clone();
+        };
+        // Manual implementations could still provide no variants.
+        if len == 0 {
+            return self.current.clone();
+        }
+        let ptr = self.update.ptr;
+        self.update.ptr = match (op, S::EDGE) {
+            (ShiftOp::Advance, ShiftEdge::Wrap) => (ptr + 1) % len,
+            (ShiftOp::Advance, ShiftEdge::Clamp) => (ptr + 1).min(len - 1),
+            (ShiftOp::Retreat, ShiftEdge::Wrap) => (ptr + len - 1) % len,
+            (ShiftOp::Retreat, ShiftEdge::Clamp) => ptr.saturating_sub(1),
+        };
+        variants[self.update.ptr].clone()
+    }
+}
+
+/// Creates a command which schedules a shift operation.
+fn shift_op_command<S: ShiftState>(
+    local: Option<Entity>,
+    op: ShiftOp,
+) -> ModifyStateDataCommand<S, impl FnOnce(&mut StateData<S>) + Send + 'static> {
+    ModifyStateDataCommand::new(local, move |state: &mut StateData<S>| {
+        state.update.op = Some(op);
+    })
+}
+
+/// Methods for shifting states.
+/// Only one operation can be applied per state update, a new request overwrites the pending one.
+///
+/// Depending on which medium this is called on, those methods will have:
+/// - immediate effect: [`World`], [`SubApp`](bevy_app::SubApp) and [`App`](bevy_app::App),
+/// - deferred effect: [`Commands`].
+pub trait ShiftStatesExt {
+    /// Shifts the state to the next variant.
+    fn advance_state<S: ShiftState>(&mut self, local: Option<Entity>) -> &mut Self;
+
+    /// Shifts the state to the previous variant.
+    fn retreat_state<S: ShiftState>(&mut self, local: Option<Entity>) -> &mut Self;
+}
+
+impl ShiftStatesExt for Commands<'_, '_> {
+    fn advance_state<S: ShiftState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.queue(shift_op_command::<S>(local, ShiftOp::Advance));
+        self
+    }
+
+    fn retreat_state<S: ShiftState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.queue(shift_op_command::<S>(local, ShiftOp::Retreat));
+        self
+    }
+}
+
+impl ShiftStatesExt for World {
+    fn advance_state<S: ShiftState>(&mut self, local: Option<Entity>) -> &mut Self {
+        shift_op_command::<S>(local, ShiftOp::Advance)
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+
+    fn retreat_state<S: ShiftState>(&mut self, local: Option<Entity>) -> &mut Self {
+        shift_op_command::<S>(local, ShiftOp::Retreat)
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl ShiftStatesExt for bevy_app::SubApp {
+    fn advance_state<S: ShiftState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.world_mut().advance_state::<S>(local);
+        self
+    }
+
+    fn retreat_state<S: ShiftState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.world_mut().retreat_state::<S>(local);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl ShiftStatesExt for bevy_app::App {
+    fn advance_state<S: ShiftState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.main_mut().advance_state::<S>(local);
+        self
+    }
+
+    fn retreat_state<S: ShiftState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.main_mut().retreat_state::<S>(local);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{schedule::Schedules, world::World};
+    use bevy_state_macros::Variants;
+
+    use super::{ShiftEdge, ShiftStatesExt, ShiftUpdate, Variants};
+    use crate::{
+        self as bevy_state_v3, commands::CoreStatesExt, components::StateData, config::StateConfig,
+        state::State, state_set::StateSetData, system_set::StateUpdates,
+    };
+
+    #[derive(Variants, Clone, Debug, PartialEq)]
+    enum Wheel {
+        A,
+        B,
+        C,
+    }
+
+    impl State for Wheel {
+        type Dependencies = ();
+        type Update = ShiftUpdate<Self>;
+        type Repr = Self;
+
+        fn update(
+            state: &mut StateData<Self>,
+            _: StateSetData<'_, Self::Dependencies>,
+        ) -> Self::Repr {
+            state.apply_shift_op()
+        }
+    }
+
+    #[derive(Variants, Clone, Debug, PartialEq)]
+    #[variants(clamp)]
+    enum Tutorial {
+        A,
+        B,
+        C,
+    }
+
+    impl State for Tutorial {
+        type Dependencies = ();
+        type Update = ShiftUpdate<Self>;
+        type Repr = Self;
+
+        fn update(
+            state: &mut StateData<Self>,
+            _: StateSetData<'_, Self::Dependencies>,
+        ) -> Self::Repr {
+            state.apply_shift_op()
+        }
+    }
+
+    macro_rules! assert_state {
+        ($world:expr, $ty:ident, $state:expr) => {
+            assert_eq!(
+                $world
+                    .query::<&StateData<$ty>>()
+                    .single($world)
+                    .unwrap()
+                    .current,
+                $state
+            );
+        };
+    }
+
+    #[test]
+    fn shift_wrap_and_clamp() {
+        let mut world = World::new();
+        let world = &mut world;
+        world.init_resource::<Schedules>();
+        world.register_state::<Wheel>(StateConfig::empty());
+        world.register_state::<Tutorial>(StateConfig::empty());
+        world.init_state(None, Wheel::B);
+        world.init_state(None, Tutorial::B);
+
+        world.advance_state::<Wheel>(None);
+        world.advance_state::<Tutorial>(None);
+        world.run_schedule(StateUpdates);
+        assert_state!(world, Wheel, Wheel::C);
+        assert_state!(world, Tutorial, Tutorial::C);
+
+        world.advance_state::<Wheel>(None);
+        world.advance_state::<Tutorial>(None);
+        world.run_schedule(StateUpdates);
+        assert_state!(world, Wheel, Wheel::A);
+        assert_state!(world, Tutorial, Tutorial::C);
+
+        world.retreat_state::<Wheel>(None);
+        world.retreat_state::<Tutorial>(None);
+        world.run_schedule(StateUpdates);
+        assert_state!(world, Wheel, Wheel::C);
+        assert_state!(world, Tutorial, Tutorial::B);
+    }
+
+    #[test]
+    fn derived_variants() {
+        assert_eq!(Wheel::variants(), &[Wheel::A, Wheel::B, Wheel::C]);
+        assert_eq!(Wheel::EDGE, ShiftEdge::Wrap);
+        assert_eq!(
+            Tutorial::variants(),
+            &[Tutorial::A, Tutorial::B, Tutorial::C]
+        );
+        assert_eq!(Tutorial::EDGE, ShiftEdge::Clamp);
+    }
+
+    #[test]
+    fn shift_local() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Wheel>(StateConfig::empty());
+        world.register_state::<Tutorial>(StateConfig::empty());
+        let local = world.spawn_empty().id();
+        world.init_state(Some(local), Wheel::A);
+        world.init_state(Some(local), Tutorial::A);
+
+        world.retreat_state::<Wheel>(Some(local));
+        world.retreat_state::<Tutorial>(Some(local));
+        world.run_schedule(StateUpdates);
+        let wheel = world.get::<StateData<Wheel>>(local).unwrap();
+        assert_eq!(wheel.current(), &Wheel::C);
+        let tutorial = world.get::<StateData<Tutorial>>(local).unwrap();
+        assert_eq!(tutorial.current(), &Tutorial::A);
+
+        world.advance_state::<Wheel>(Some(local));
+        world.advance_state::<Tutorial>(Some(local));
+        world.run_schedule(StateUpdates);
+        let wheel = world.get::<StateData<Wheel>>(local).unwrap();
+        assert_eq!(wheel.current(), &Wheel::A);
+        let tutorial = world.get::<StateData<Tutorial>>(local).unwrap();
+        assert_eq!(tutorial.current(), &Tutorial::B);
+    }
+}
@@ -0,0 +1,387 @@
+//! Stack based state update backend.
+//!
+//! The top of the stack is the current state, the remaining values wait underneath it.
+//! This is useful for layered states, like a pause menu opened over gameplay
+//! or a confirmation dialog opened over the pause menu.
+//!
+//! The state has to be optional, it's `None` when the stack is empty:
+//! ```rs
+//! #[derive(Debug, Clone, PartialEq)]
+//! enum Menu {
+//!     Pause,
+//!     Options,
+//!     Confirm,
+//! }
+//!
+//! impl State for Menu {
+//!     type Dependencies = ();
+//!     type Update = StackUpdate<Self>;
+//!     type Repr = Option<Self>;
+//!
+//!     fn update(state: &mut StateData<Self>, _: StateSetData<'_, Self::Dependencies>) -> Self::Repr {
+//!         state.apply_stack_op()
+//!     }
+//! }
+//! ```
+//! The stack is then modified through [`StackStatesExt`] methods.
+
+use bevy_ecs::prelude::{Command, Commands, Entity, World};
+
+use crate::{
+    commands::ModifyStateDataCommand,
+    components::StateData,
+    error::warn_error,
+    state::{State, StateUpdate},
+};
+
+/// States which use the [`StackUpdate`] backend.
+pub trait StackState: State<Repr = Option<Self>, Update = StackUpdate<Self>> {}
+
+impl<S: State<Repr = Option<S>, Update = StackUpdate<S>>> StackState for S {}
+
+/// Operation on the state stack.
+#[derive(Debug, Clone)]
+pub enum StackOp<S> {
+    /// Adds a value to the top of the stack.
+    Push(S),
+    /// Removes a value from the top of the stack.
+    Pop,
+    /// Replaces the value on top of the stack.
+    /// Same as [`StackOp::Push`] if the stack is empty.
+    ReplaceTop(S),
+    /// Removes all values from the stack.
+    Clear,
+}
+
+/// Stack update data structure for states.
+#[derive(Debug, Clone)]
+pub struct StackUpdate<S: State> {
+    /// The stack except the top value, which is stored as the `current` state.
+    stack: Vec<S>,
+    /// Pending operation on the stack.
+    op: Option<StackOp<S>>,
+}
+
+impl<S: State> Default for StackUpdate<S> {
+    fn default() -> Self {
+        Self {
+            stack: Default::default(),
+            op: Default::default(),
+        }
+    }
+}
+
+impl<S: State> StateUpdate for StackUpdate<S> {
+    fn should_update(&self) -> bool {
+        self.op.is_some()
+    }
+
+    fn post_update(&mut self) {
+        self.op.take();
+    }
+}
+
+impl<S: State> StackUpdate<S> {
+    /// Returns the pending stack operation.
+    pub fn op(&self) -> Option<&StackOp<S>> {
+        self.op.as_ref()
+    }
+}
+
+impl<S: StackState> StateData<S> {
+    /// Applies the pending stack operation and returns the new top of the stack.
+    /// Meant to be called from [`State::update`].
+    ///
+    /// If no operation is pending, which happens when a dependency was updated,
+    /// the current value is kept.
+    pub fn apply_stack_op(&mut self) -> Option<S> {
+        let Some(op) = self.update.op.take() else {
+            return self.current.clone();
+        };
+        match op {
+            StackOp::Push(value) => {
+                if let Some(current) = self.current.clone() {
+                    self.update.stack.push(current);
+                }
+                Some(value)
+            }
+            StackOp::Pop => self.update.stack.pop(),
+            StackOp::ReplaceTop(value) => Some(value),
+            StackOp::Clear => {
+                self.update.stack.clear();
+                None
+            }
+        }
+    }
+
+    /// Returns the number of values on the stack, including the current state.
+    pub fn stack_depth(&self) -> usize {
+        self.update.stack.len() + usize::from(self.current.is_some())
+    }
+
+    /// Returns all values on the stack, from bottom to top.
+    /// The last value is the current state.
+    pub fn stack(&self) -> impl Iterator<Item = &S> {
+        self.update.stack.iter().chain(self.current.iter())
+    }
+}
+
+/// Creates a command which schedules a stack operation.
+fn stack_op_command<S: StackState>(
+    local: Option<Entity>,
+    op: StackOp<S>,
+) -> ModifyStateDataCommand<S, impl FnOnce(&mut StateData<S>) + Send + 'static> {
+    ModifyStateDataCommand::new(local, move |state: &mut StateData<S>| {
+        state.update.op = Some(op);
+    })
+}
+
+/// Methods for requesting stack operations.
+/// Only one operation can be applied per state update, a new request overwrites the pending one.
+///
+/// Depending on which medium this is called on, those methods will have:
+/// - immediate effect: [`World`], [`SubApp`](bevy_app::SubApp) and [`App`](bevy_app::App),
+/// - deferred effect: [`Commands`].
+pub trait StackStatesExt {
+    /// Pushes a new state to the top of the stack.
+    fn push_state<S: StackState>(&mut self, local: Option<Entity>, value: S) -> &mut Self;
+
+    /// Pops the top state from the stack.
+    /// The state becomes `None` if no more states are left on the stack.
+    fn pop_state<S: StackState>(&mut self, local: Option<Entity>) -> &mut Self;
+
+    /// Replaces the top state of the stack.
+    fn replace_top<S: StackState>(&mut self, local: Option<Entity>, value: S) -> &mut Self;
+
+    /// Removes all states from the stack.
+    fn clear_stack<S: StackState>(&mut self, local: Option<Entity>) -> &mut Self;
+}
+
+impl StackStatesExt for Commands<'_, '_> {
+    fn push_state<S: StackState>(&mut self, local: Option<Entity>, value: S) -> &mut Self {
+        self.queue(stack_op_command(local, StackOp::Push(value)));
+        self
+    }
+
+    fn pop_state<S: StackState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.queue(stack_op_command(local, StackOp::<S>::Pop));
+        self
+    }
+
+    fn replace_top<S: StackState>(&mut self, local: Option<Entity>, value: S) -> &mut Self {
+        self.queue(stack_op_command(local, StackOp::ReplaceTop(value)));
+        self
+    }
+
+    fn clear_stack<S: StackState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.queue(stack_op_command(local, StackOp::<S>::Clear));
+        self
+    }
+}
+
+impl StackStatesExt for World {
+    fn push_state<S: StackState>(&mut self, local: Option<Entity>, value: S) -> &mut Self {
+        stack_op_command(local, StackOp::Push(value))
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+
+    fn pop_state<S: StackState>(&mut self, local: Option<Entity>) -> &mut Self {
+        stack_op_command(local, StackOp::<S>::Pop)
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+
+    fn replace_top<S: StackState>(&mut self, local: Option<Entity>, value: S) -> &mut Self {
+        stack_op_command(local, StackOp::ReplaceTop(value))
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+
+    fn clear_stack<S: StackState>(&mut self, local: Option<Entity>) -> &mut Self {
+        stack_op_command(local, StackOp::<S>::Clear)
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl StackStatesExt for bevy_app::SubApp {
+    fn push_state<S: StackState>(&mut self, local: Option<Entity>, value: S) -> &mut Self {
+        self.world_mut().push_state(local, value);
+        self
+    }
+
+    fn pop_state<S: StackState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.world_mut().pop_state::<S>(local);
+        self
+    }
+
+    fn replace_top<S: StackState>(&mut self, local: Option<Entity>, value: S) -> &mut Self {
+        self.world_mut().replace_top(local, value);
+        self
+    }
+
+    fn clear_stack<S: StackState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.world_mut().clear_stack::<S>(local);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl StackStatesExt for bevy_app::App {
+    fn push_state<S: StackState>(&mut self, local: Option<Entity>, value: S) -> &mut Self {
+        self.main_mut().push_state(local, value);
+        self
+    }
+
+    fn pop_state<S: StackState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.main_mut().pop_state::<S>(local);
+        self
+    }
+
+    fn replace_top<S: StackState>(&mut self, local: Option<Entity>, value: S) -> &mut Self {
+        self.main_mut().replace_top(local, value);
+        self
+    }
+
+    fn clear_stack<S: StackState>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.main_mut().clear_stack::<S>(local);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{entity::Entity, schedule::Schedules, world::World};
+
+    use super::{StackStatesExt, StackUpdate};
+    use crate::{
+        commands::CoreStatesExt, components::StateData, config::StateConfig, state::State,
+        state_set::StateSetData, system_set::StateUpdates,
+    };
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Menu {
+        Pause,
+        Options,
+        Confirm,
+    }
+
+    impl State for Menu {
+        type Dependencies = ();
+        type Update = StackUpdate<Self>;
+        type Repr = Option<Self>;
+
+        fn update(
+            state: &mut StateData<Self>,
+            _: StateSetData<'_, Self::Dependencies>,
+        ) -> Self::Repr {
+            state.apply_stack_op()
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Dialog {
+        Save,
+        Quit,
+    }
+
+    impl State for Dialog {
+        type Dependencies = ();
+        type Update = StackUpdate<Self>;
+        type Repr = Option<Self>;
+
+        fn update(
+            state: &mut StateData<Self>,
+            _: StateSetData<'_, Self::Dependencies>,
+        ) -> Self::Repr {
+            state.apply_stack_op()
+        }
+
+        fn guard(
+            _state: &StateData<Self>,
+            next: &Self::Repr,
+            _: StateSetData<'_, Self::Dependencies>,
+        ) -> bool {
+            next != &Some(Dialog::Quit)
+        }
+    }
+
+    fn assert_stack(world: &mut World, expected: &[Menu]) {
+        let state = world.query::<&StateData<Menu>>().single(world).unwrap();
+        assert_eq!(state.stack_depth(), expected.len());
+        assert!(state.stack().eq(expected.iter()));
+        assert_eq!(state.current().as_ref(), expected.last());
+    }
+
+    fn test_stack(world: &mut World, local: Option<Entity>) {
+        world.init_resource::<Schedules>();
+        world.register_state::<Menu>(StateConfig::empty());
+        world.init_state(local, None::<Menu>);
+        assert_stack(world, &[]);
+
+        world.push_state(local, Menu::Pause);
+        world.run_schedule(StateUpdates);
+        world.push_state(local, Menu::Options);
+        world.run_schedule(StateUpdates);
+        assert_stack(world, &[Menu::Pause, Menu::Options]);
+
+        world.replace_top(local, Menu::Confirm);
+        world.run_schedule(StateUpdates);
+        assert_stack(world, &[Menu::Pause, Menu::Confirm]);
+
+        world.pop_state::<Menu>(local);
+        world.run_schedule(StateUpdates);
+        assert_stack(world, &[Menu::Pause]);
+
+        world.push_state(local, Menu::Options);
+        world.run_schedule(StateUpdates);
+        world.clear_stack::<Menu>(local);
+        world.run_schedule(StateUpdates);
+        assert_stack(world, &[]);
+
+        world.pop_state::<Menu>(local);
+        world.run_schedule(StateUpdates);
+        assert_stack(world, &[]);
+    }
+
+    #[test]
+    fn global_stack() {
+        let mut world = World::new();
+        test_stack(&mut world, None);
+    }
+
+    #[test]
+    fn local_stack() {
+        let mut world = World::new();
+        let local = Some(world.spawn_empty().id());
+        test_stack(&mut world, local);
+    }
+
+    #[test]
+    fn rejected_push() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Dialog>(StateConfig::empty());
+        world.init_state(None, None::<Dialog>);
+        world.push_state(None, Dialog::Save);
+        world.run_schedule(StateUpdates);
+
+        // Rejected push leaves the stack untouched.
+        world.push_state(None, Dialog::Quit);
+        world.run_schedule(StateUpdates);
+        let state = world.query::<&StateData<Dialog>>().single(&world).unwrap();
+        assert_eq!(state.stack_depth(), 1);
+        assert_eq!(state.current(), &Some(Dialog::Save));
+
+        world.pop_state::<Dialog>(None);
+        world.run_schedule(StateUpdates);
+        let state = world.query::<&StateData<Dialog>>().single(&world).unwrap();
+        assert_eq!(state.current(), &None);
+    }
+}
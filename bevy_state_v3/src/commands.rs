@@ -1,16 +1,20 @@
 //! Helper methods for interacting with states.
 
+use std::marker::PhantomData;
+
 use bevy_ecs::{
     prelude::{Command, Commands, Entity, Result, With, World},
     query::QuerySingleError,
 };
-use bevy_log::warn;
 
 use crate::{
-    components::StateData,
+    components::{RegisteredState, StateData, StateRegistration, StateUpdateOverride},
     config::StateConfig,
-    state::{State, StateRepr},
+    error::{StateError, warn_error},
+    state::{State, StateRepr, StateUpdate, rebuild_state_updates},
+    state_set::StateSet,
     util::GlobalMarker,
+    validation::{validate_dependencies, validate_registration},
 };
 
 struct InitializeStateCommand<S: State> {
@@ -24,8 +28,8 @@ impl<S: State> InitializeStateCommand<S> {
     }
 }
 
-impl<S: State + Send + Sync + 'static> Command<Result> for InitializeStateCommand<S> {
-    fn apply(self, world: &mut World) -> Result {
+impl<S: State> InitializeStateCommand<S> {
+    fn try_apply(self, world: &mut World) -> Result<(), StateError> {
         let entity = match self.local {
             Some(entity) => entity,
             None => {
@@ -36,37 +40,35 @@ impl<S: State + Send + Sync + 'static> Command<Result> for InitializeStateComman
                     Ok(entity) => entity,
                     Err(QuerySingleError::NoEntities(_)) => world.spawn(GlobalMarker).id(),
                     Err(QuerySingleError::MultipleEntities(_)) => {
-                        warn!(
-                            "Insert global state command failed, multiple entities have the `GlobalStateMarker` component."
-                        );
-                        return Ok(());
+                        return Err(StateError::MultipleGlobalEntities);
                     }
                 }
             }
         };
 
-        // Register storage for state `S`.
-        let state_data = world
-            .query::<&mut StateData<S>>()
-            .get_mut(world, entity)
-            .ok();
-        match state_data {
-            None => {
-                world
-                    .entity_mut(entity)
-                    .insert(StateData::<S>::new(self.initial));
-            }
-            Some(_) => {
-                warn!(
-                    "Attempted to initialize state {}, but it was already present.",
-                    disqualified::ShortName::of::<S>()
-                );
-            }
+        let Ok(entity_ref) = world.get_entity(entity) else {
+            return Err(StateError::NoSuchEntity(entity));
+        };
+        if entity_ref.contains::<StateData<S>>() {
+            return Err(StateError::already_initialized::<S>(entity));
         }
+        validate_dependencies::<S>(world, entity)?;
+
+        // Register storage for state `S`.
+        world
+            .entity_mut(entity)
+            .insert(StateData::<S>::new(self.initial));
+        auto_init_dependents::<S>(world, entity);
         Ok(())
     }
 }
 
+impl<S: State> Command<Result> for InitializeStateCommand<S> {
+    fn apply(self, world: &mut World) -> Result {
+        self.try_apply(world).map_err(Into::into)
+    }
+}
+
 struct WakeStateTargetCommand<S: IntoStateUpdate> {
     local: Option<Entity>,
     update: S::Update,
@@ -81,43 +83,248 @@ impl<S: IntoStateUpdate> WakeStateTargetCommand<S> {
     }
 }
 
+/// Initializes state `S` on the entity, computing its initial value from the dependencies.
+/// Used for states registered with [`StateConfig::with_auto_init`].
+pub(crate) fn auto_init_state<S: State>(world: &mut World, entity: Entity) {
+    let Some(placeholder) = <S::Repr as StateRepr>::placeholder() else {
+        return;
+    };
+    let mut state = StateData::<S>::new(placeholder);
+    let Ok((dependencies, update_override)) = world
+        .query::<(
+            <S::Dependencies as StateSet>::Query,
+            Option<&StateUpdateOverride<S>>,
+        )>()
+        .get_mut(world, entity)
+    else {
+        return;
+    };
+    let initial = match update_override {
+        Some(update_override) => update_override.apply(&mut state, dependencies),
+        None => S::update(&mut state, dependencies),
+    };
+    world
+        .entity_mut(entity)
+        .insert(StateData::<S>::new(initial));
+    auto_init_dependents::<S>(world, entity);
+}
+
+/// Initializes all automatically initialized states which depend on state `S`
+/// and have all of their dependencies present on the entity.
+fn auto_init_dependents<S: State>(world: &mut World, entity: Entity) {
+    let component = world.register_component::<StateData<S>>();
+    let dependents = world
+        .query::<&StateRegistration>()
+        .iter(world)
+        .filter(|r| r.dependencies().contains(&component))
+        .filter_map(|r| Some((r.component(), r.dependencies().to_vec(), r.auto_init?)))
+        .collect::<Vec<_>>();
+    for (component, dependencies, auto_init) in dependents {
+        // Other dependents could have initialized this state already.
+        let entity_ref = world.entity(entity);
+        if entity_ref.contains_id(component)
+            || !dependencies.iter().all(|d| entity_ref.contains_id(*d))
+        {
+            continue;
+        }
+        auto_init(world, entity);
+    }
+}
+
 /// Conversion from local/global [`Option<Entity>`] to [`Entity`] for states.
-pub fn state_target_entity(world: &mut World, local: Option<Entity>) -> Option<Entity> {
+pub fn state_target_entity(world: &mut World, local: Option<Entity>) -> Result<Entity, StateError> {
     match local {
-        Some(entity) => Some(entity),
+        Some(entity) => Ok(entity),
         None => {
             match world
                 .query_filtered::<Entity, With<GlobalMarker>>()
                 .single(world)
             {
-                Err(QuerySingleError::NoEntities(_)) => {
-                    warn!("No global state entity exists.");
-                    None
-                }
+                Err(QuerySingleError::NoEntities(_)) => Err(StateError::NoGlobalEntity),
                 Err(QuerySingleError::MultipleEntities(_)) => {
-                    warn!("Multiple global state entities exist.");
-                    None
+                    Err(StateError::MultipleGlobalEntities)
                 }
-                Ok(entity) => Some(entity),
+                Ok(entity) => Ok(entity),
             }
         }
     }
 }
 
+impl<S: IntoStateUpdate> WakeStateTargetCommand<S> {
+    fn try_apply(self, world: &mut World) -> Result<(), StateError> {
+        let entity = state_target_entity(world, self.local)?;
+        let Ok(mut entity) = world.get_entity_mut(entity) else {
+            return Err(StateError::NoSuchEntity(entity));
+        };
+        let Some(mut state) = entity.get_mut::<StateData<S>>() else {
+            return Err(StateError::missing_state::<S>(entity.id()));
+        };
+        state.update.merge(self.update);
+        // Newer request cancels the delayed one.
+        #[cfg(feature = "bevy_time")]
+        entity.remove::<crate::delayed::DelayedUpdate<S>>();
+        Ok(())
+    }
+}
+
 impl<S: IntoStateUpdate> Command<Result> for WakeStateTargetCommand<S> {
     fn apply(self, world: &mut World) -> Result {
-        let Some(entity) = state_target_entity(world, self.local) else {
-            return Ok(());
+        self.try_apply(world).map_err(Into::into)
+    }
+}
+
+struct RemoveStateCommand<S: State> {
+    local: Option<Entity>,
+    _state: PhantomData<S>,
+}
+
+impl<S: State> RemoveStateCommand<S> {
+    fn new(local: Option<Entity>) -> Self {
+        Self {
+            local,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<S: State> Command<Result> for RemoveStateCommand<S> {
+    fn apply(self, world: &mut World) -> Result {
+        let entity = state_target_entity(world, self.local)?;
+        let Ok(entity_ref) = world.get_entity(entity) else {
+            return Err(StateError::NoSuchEntity(entity).into());
         };
+        if !entity_ref.contains::<StateData<S>>() {
+            return Err(StateError::missing_state::<S>(entity).into());
+        }
+
+        // Find all states which directly or indirectly depend on this state.
+        let registrations = world
+            .query::<&StateRegistration>()
+            .iter(world)
+            .map(|r| {
+                (
+                    r.component(),
+                    r.dependencies().to_vec(),
+                    r.order(),
+                    r.delayed,
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut removed = vec![(world.register_component::<StateData<S>>(), S::ORDER)];
+        let mut i = 0;
+        while let Some(&(parent, _)) = removed.get(i) {
+            for (component, dependencies, order, _) in registrations.iter() {
+                if dependencies.contains(&parent) && !removed.iter().any(|(c, _)| c == component) {
+                    removed.push((*component, *order));
+                }
+            }
+            i += 1;
+        }
+
+        // Remove from leaf states to root states, so dependencies outlive dependents.
+        removed.sort_by_key(|(_, order)| core::cmp::Reverse(*order));
+        let delayed = registrations
+            .iter()
+            .filter(|(component, ..)| removed.iter().any(|(c, _)| c == component))
+            .filter_map(|(.., delayed)| *delayed)
+            .collect::<Vec<_>>();
         let mut entity = world.entity_mut(entity);
+        for component in delayed {
+            entity.remove_by_id(component);
+        }
+        for (component, _) in removed {
+            entity.remove_by_id(component);
+        }
+        Ok(())
+    }
+}
+
+struct ResetStateCommand<S: State> {
+    local: Option<Entity>,
+    _state: PhantomData<S>,
+}
+
+impl<S: State> ResetStateCommand<S> {
+    fn new(local: Option<Entity>) -> Self {
+        Self {
+            local,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<S: State> Command<Result> for ResetStateCommand<S> {
+    fn apply(self, world: &mut World) -> Result {
+        let entity = state_target_entity(world, self.local)?;
+        let Ok(mut entity) = world.get_entity_mut(entity) else {
+            return Err(StateError::NoSuchEntity(entity).into());
+        };
         let Some(mut state) = entity.get_mut::<StateData<S>>() else {
-            warn!(
-                "Set state command failed, entity does not have state {}",
-                disqualified::ShortName::of::<S>()
-            );
-            return Ok(());
+            return Err(StateError::missing_state::<S>(entity.id()).into());
+        };
+        state.update.reset();
+        state.reset = true;
+        #[cfg(feature = "bevy_time")]
+        entity.remove::<crate::delayed::DelayedUpdate<S>>();
+        Ok(())
+    }
+}
+
+struct UnregisterStateCommand<S: State>(PhantomData<S>);
+
+impl<S: State> Command<Result> for UnregisterStateCommand<S> {
+    fn apply(self, world: &mut World) -> Result {
+        let Ok((registration, state_registration)) = world
+            .query_filtered::<(Entity, &StateRegistration), With<RegisteredState<S>>>()
+            .single(world)
+        else {
+            return Err(StateError::not_registered::<S>().into());
         };
-        state.update = self.update;
+        let observers = state_registration.observers.clone();
+        for observer in observers {
+            world.despawn(observer);
+        }
+        world.despawn(registration);
+        rebuild_state_updates(world);
+        Ok(())
+    }
+}
+
+/// Command for modifying state data in place.
+/// Used by update backends that operate on their update data directly.
+pub(crate) struct ModifyStateDataCommand<S: State, F> {
+    local: Option<Entity>,
+    modify: F,
+    _state: PhantomData<S>,
+}
+
+impl<S: State, F> ModifyStateDataCommand<S, F> {
+    pub(crate) fn new(local: Option<Entity>, modify: F) -> Self {
+        Self {
+            local,
+            modify,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<S, F> Command<Result> for ModifyStateDataCommand<S, F>
+where
+    S: State,
+    F: FnOnce(&mut StateData<S>) + Send + 'static,
+{
+    fn apply(self, world: &mut World) -> Result {
+        let entity = state_target_entity(world, self.local)?;
+        let Ok(mut entity) = world.get_entity_mut(entity) else {
+            return Err(StateError::NoSuchEntity(entity).into());
+        };
+        let Some(mut state) = entity.get_mut::<StateData<S>>() else {
+            return Err(StateError::missing_state::<S>(entity.id()).into());
+        };
+        (self.modify)(&mut *state);
+        // Newer request cancels the delayed one.
+        #[cfg(feature = "bevy_time")]
+        entity.remove::<crate::delayed::DelayedUpdate<S>>();
         Ok(())
     }
 }
@@ -142,7 +349,9 @@ where
 /// Core methods for interacting with states:
 /// - registering state machinery in the world,
 /// - initializing states,
-/// - updating them.
+/// - updating them,
+/// - removing and resetting them,
+/// - unregistering state machinery.
 ///
 /// Those methods require providing all relevant data.
 /// Additional methods can be derived from them by using default values.
@@ -157,6 +366,23 @@ pub trait CoreStatesExt {
     fn init_state<R: StateRepr>(&mut self, local: Option<Entity>, initial: R) -> &mut Self;
 
     fn update_state<S: IntoStateUpdate>(&mut self, local: Option<Entity>, update: S) -> &mut Self;
+
+    /// Removes the state along with all states that depend on it.
+    /// States are removed from leaf to root, each triggering [`OnDeinit`](crate::transitions::OnDeinit).
+    fn remove_state<S: State>(&mut self, local: Option<Entity>) -> &mut Self;
+
+    /// Resets the state to its initial value during next state update.
+    /// Pending update data, including backend history, is cleared.
+    /// States with dependencies are recomputed through [`State::update`] starting from the initial value,
+    /// so the result can differ from the initial value if the dependencies don't allow it.
+    fn reset_state<S: State>(&mut self, local: Option<Entity>) -> &mut Self;
+
+    /// Unregisters machinery of the state, after which the state can be registered again.
+    /// Observers are despawned and the [`StateUpdates`](crate::system_set::StateUpdates) schedule
+    /// is rebuilt from the remaining registrations, which removes systems of this state.
+    /// Systems added to that schedule outside of state registration are removed as well.
+    /// Existing state data is left untouched, but will no longer be updated.
+    fn unregister_state<S: State>(&mut self) -> &mut Self;
 }
 
 impl CoreStatesExt for Commands<'_, '_> {
@@ -176,6 +402,21 @@ impl CoreStatesExt for Commands<'_, '_> {
         self.queue(WakeStateTargetCommand::<S>::new(local, update));
         self
     }
+
+    fn remove_state<S: State>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.queue(RemoveStateCommand::<S>::new(local));
+        self
+    }
+
+    fn reset_state<S: State>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.queue(ResetStateCommand::<S>::new(local));
+        self
+    }
+
+    fn unregister_state<S: State>(&mut self) -> &mut Self {
+        self.queue(UnregisterStateCommand::<S>(PhantomData));
+        self
+    }
 }
 
 impl CoreStatesExt for World {
@@ -187,14 +428,35 @@ impl CoreStatesExt for World {
     fn init_state<R: StateRepr>(&mut self, local: Option<Entity>, initial: R) -> &mut Self {
         InitializeStateCommand::<R::State>::new(local, initial)
             .apply(self)
-            .unwrap();
+            .unwrap_or_else(warn_error);
         self
     }
 
     fn update_state<S: IntoStateUpdate>(&mut self, local: Option<Entity>, update: S) -> &mut Self {
         WakeStateTargetCommand::<S>::new(local, update)
             .apply(self)
-            .unwrap();
+            .unwrap_or_else(warn_error);
+        self
+    }
+
+    fn remove_state<S: State>(&mut self, local: Option<Entity>) -> &mut Self {
+        RemoveStateCommand::<S>::new(local)
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+
+    fn reset_state<S: State>(&mut self, local: Option<Entity>) -> &mut Self {
+        ResetStateCommand::<S>::new(local)
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+
+    fn unregister_state<S: State>(&mut self) -> &mut Self {
+        UnregisterStateCommand::<S>(PhantomData)
+            .apply(self)
+            .unwrap_or_else(warn_error);
         self
     }
 }
@@ -215,6 +477,21 @@ impl CoreStatesExt for bevy_app::SubApp {
         self.world_mut().update_state::<S>(local, update);
         self
     }
+
+    fn remove_state<S: State>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.world_mut().remove_state::<S>(local);
+        self
+    }
+
+    fn reset_state<S: State>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.world_mut().reset_state::<S>(local);
+        self
+    }
+
+    fn unregister_state<S: State>(&mut self) -> &mut Self {
+        self.world_mut().unregister_state::<S>();
+        self
+    }
 }
 
 #[cfg(feature = "bevy_app")]
@@ -233,4 +510,69 @@ impl CoreStatesExt for bevy_app::App {
         self.main_mut().update_state::<S>(local, update);
         self
     }
+
+    fn remove_state<S: State>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.main_mut().remove_state::<S>(local);
+        self
+    }
+
+    fn reset_state<S: State>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.main_mut().reset_state::<S>(local);
+        self
+    }
+
+    fn unregister_state<S: State>(&mut self) -> &mut Self {
+        self.main_mut().unregister_state::<S>();
+        self
+    }
+}
+
+/// Fallible methods for interacting with states, which return errors to the caller
+/// instead of logging them.
+pub trait TryStatesExt {
+    /// Registers machinery of the state, failing if it's already registered
+    /// or if any of its dependencies are not registered yet.
+    fn try_register_state<S: State>(&mut self, config: StateConfig) -> Result<(), StateError>;
+
+    /// Initializes the state, failing if it's already present.
+    fn try_init_state<R: StateRepr>(
+        &mut self,
+        local: Option<Entity>,
+        initial: R,
+    ) -> Result<(), StateError>;
+
+    /// Requests a state update, failing if the state doesn't exist.
+    fn try_update_state<S: IntoStateUpdate>(
+        &mut self,
+        local: Option<Entity>,
+        update: S,
+    ) -> Result<(), StateError>;
+}
+
+impl TryStatesExt for World {
+    fn try_register_state<S: State>(&mut self, config: StateConfig) -> Result<(), StateError> {
+        let mut query = self.query_filtered::<(), With<RegisteredState<S>>>();
+        if query.iter(self).next().is_some() {
+            return Err(StateError::already_registered::<S>());
+        }
+        validate_registration::<S>(self)?;
+        S::register_state(self, config);
+        Ok(())
+    }
+
+    fn try_init_state<R: StateRepr>(
+        &mut self,
+        local: Option<Entity>,
+        initial: R,
+    ) -> Result<(), StateError> {
+        InitializeStateCommand::<R::State>::new(local, initial).try_apply(self)
+    }
+
+    fn try_update_state<S: IntoStateUpdate>(
+        &mut self,
+        local: Option<Entity>,
+        update: S,
+    ) -> Result<(), StateError> {
+        WakeStateTargetCommand::<S>::new(local, update).try_apply(self)
+    }
 }
@@ -1,14 +1,26 @@
 //! State related components.
 
+use core::any::TypeId;
 use std::marker::PhantomData;
 
-use bevy_ecs::component::{
-    Component, ComponentId, ComponentsRegistrator, Mutable, RequiredComponents, StorageType,
+use bevy_ecs::{
+    component::{
+        Component, ComponentId, ComponentsRegistrator, Mutable, RequiredComponents, StorageType,
+    },
+    entity::Entity,
+    query::With,
+    schedule::Schedule,
+    system::Query,
+    world::World,
 };
 #[cfg(feature = "bevy_reflect")]
 use bevy_reflect::prelude::*;
 
-use crate::{state::State, state_set::StateSet};
+use crate::{
+    config::StateConfig,
+    state::State,
+    state_set::{StateSet, StateSetData},
+};
 
 /// Component that stores state data.
 #[derive(Debug)]
@@ -35,6 +47,15 @@ pub struct StateData<S: State> {
     /// Whether this state was updated in the last [`StateTransition`] schedule.
     /// For a standard use case, this happens once per frame.
     pub(crate) is_updated: bool,
+
+    /// Value the state was initialized with.
+    pub(crate) initial: S::Repr,
+
+    /// Whether the state should be reset to its initial value during next update.
+    pub(crate) reset: bool,
+
+    /// Whether the initial value should be entered during next update.
+    pub(crate) initial_transition: bool,
 }
 
 impl<S> Default for StateData<S>
@@ -49,6 +70,9 @@ where
             current: Default::default(),
             update: Default::default(),
             is_updated: false,
+            initial: Default::default(),
+            reset: false,
+            initial_transition: false,
         }
     }
 }
@@ -93,6 +117,9 @@ impl<S: State> StateData<S> {
             is_reentrant: false,
             update: S::Update::default(),
             is_updated: false,
+            initial,
+            reset: false,
+            initial_transition: false,
         }
     }
 
@@ -101,6 +128,11 @@ impl<S: State> StateData<S> {
         &self.current
     }
 
+    /// Returns the value this state was initialized with.
+    pub fn initial(&self) -> &S::Repr {
+        &self.initial
+    }
+
     /// Returns the last different state.
     /// If the current state was reentered, this value will remain unchanged,
     /// instead the [`Self::is_reentrant()`] flag will be raised.
@@ -138,6 +170,44 @@ impl<S: State> StateData<S> {
     }
 }
 
+/// Component which replaces [`State::update`] of state `S` on this entity.
+/// The [`State::guard`], dependencies and reset behavior are unchanged.
+///
+/// The override is used starting with the next update of the state.
+/// ```rs
+/// commands.entity(entity).insert(StateUpdateOverride::<Behavior>::new(|state, dependencies| {
+///     // ...
+/// }));
+/// ```
+#[derive(Component)]
+pub struct StateUpdateOverride<S: State> {
+    update:
+        Box<dyn Fn(&mut StateData<S>, StateSetData<'_, S::Dependencies>) -> S::Repr + Send + Sync>,
+}
+
+impl<S: State> StateUpdateOverride<S> {
+    /// Creates an override from the update function.
+    pub fn new(
+        update: impl Fn(&mut StateData<S>, StateSetData<'_, S::Dependencies>) -> S::Repr
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        Self {
+            update: Box::new(update),
+        }
+    }
+
+    /// Computes the next value of the state.
+    pub(crate) fn apply(
+        &self,
+        state: &mut StateData<S>,
+        dependencies: StateSetData<'_, S::Dependencies>,
+    ) -> S::Repr {
+        (self.update)(state, dependencies)
+    }
+}
+
 /// Component for tracking registered states.
 #[derive(Component)]
 pub struct RegisteredState<S: State>(PhantomData<S>);
@@ -147,3 +217,128 @@ impl<S: State> Default for RegisteredState<S> {
         Self(Default::default())
     }
 }
+
+/// Type-erased information about a registered state.
+/// Stored next to [`RegisteredState`] on the registration entity.
+#[derive(Component, Debug)]
+pub struct StateRegistration {
+    name: String,
+    type_name: &'static str,
+    type_id: TypeId,
+    sequence: u32,
+    component: ComponentId,
+    dependencies: Vec<ComponentId>,
+    dependency_type_names: Vec<&'static str>,
+    remote_dependencies: Vec<ComponentId>,
+    remote_dependency_type_names: Vec<&'static str>,
+    order: u32,
+    config: StateConfig,
+    /// Observers and registered systems of this state, despawned when the state is unregistered.
+    pub(crate) observers: Vec<Entity>,
+    /// Initializes the state on an entity, if enabled by [`StateConfig`](crate::config::StateConfig).
+    pub(crate) auto_init: Option<fn(&mut World, Entity)>,
+    /// Add systems of this registration to the [`StateUpdates`](crate::system_set::StateUpdates) schedule,
+    /// used again when the schedule is rebuilt after another state is unregistered.
+    pub(crate) systems: Vec<fn(&mut Schedule, Entity, &StateConfig)>,
+    /// Component id of the delayed update for this state, removed together with the state.
+    pub(crate) delayed: Option<ComponentId>,
+}
+
+impl StateRegistration {
+    /// Creates registration information for state `S`.
+    pub(crate) fn new<S: State>(world: &mut World, config: StateConfig) -> Self {
+        let sequence = world
+            .query::<&StateRegistration>()
+            .iter(world)
+            .map(|registration| registration.sequence + 1)
+            .max()
+            .unwrap_or(0);
+        #[cfg(feature = "bevy_time")]
+        let delayed = Some(world.register_component::<crate::delayed::DelayedUpdate<S>>());
+        #[cfg(not(feature = "bevy_time"))]
+        let delayed = None;
+        let (remote_dependencies, remote_dependency_type_names) =
+            S::remote_dependencies(world).into_iter().unzip();
+        Self {
+            name: disqualified::ShortName::of::<S>().to_string(),
+            type_name: core::any::type_name::<S>(),
+            type_id: TypeId::of::<S>(),
+            sequence,
+            component: world.register_component::<StateData<S>>(),
+            dependencies: S::Dependencies::component_ids(world),
+            dependency_type_names: S::Dependencies::type_names(),
+            remote_dependencies,
+            remote_dependency_type_names,
+            order: S::ORDER,
+            config,
+            observers: Vec::new(),
+            auto_init: None,
+            systems: Vec::new(),
+            delayed,
+        }
+    }
+
+    /// Short name of the state type.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Type name of the state.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Type id of the state.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Registration order of this state, among currently registered states.
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    /// Component id of the [`StateData`] for this state.
+    pub fn component(&self) -> ComponentId {
+        self.component
+    }
+
+    /// Component ids of the [`StateData`] for direct dependencies of this state.
+    pub fn dependencies(&self) -> &[ComponentId] {
+        &self.dependencies
+    }
+
+    /// Type names of direct dependencies of this state.
+    pub fn dependency_type_names(&self) -> &[&'static str] {
+        &self.dependency_type_names
+    }
+
+    /// Component ids of the [`StateData`] for states on other entities, which this state is synchronized from.
+    /// Only proxy states from [`remote`](crate::remote) have those.
+    pub fn remote_dependencies(&self) -> &[ComponentId] {
+        &self.remote_dependencies
+    }
+
+    /// Type names of states on other entities, which this state is synchronized from.
+    pub fn remote_dependency_type_names(&self) -> &[&'static str] {
+        &self.remote_dependency_type_names
+    }
+
+    /// Update order of this state.
+    pub fn order(&self) -> u32 {
+        self.order
+    }
+
+    /// Configuration this state was registered with.
+    pub fn config(&self) -> &StateConfig {
+        &self.config
+    }
+
+    /// Run condition which disables systems of this registration once the state is unregistered.
+    /// Systems are removed by rebuilding the schedule, unless the state is unregistered while the schedule runs.
+    pub(crate) fn is_active(
+        registration: Entity,
+    ) -> impl Fn(Query<(), With<StateRegistration>>) -> bool {
+        move |query: Query<(), With<StateRegistration>>| query.contains(registration)
+    }
+}
@@ -1,31 +1,45 @@
 //! State configuration during registration.
 
+use core::cmp::Reverse;
+
 use bevy_ecs::{
-    schedule::{IntoScheduleConfigs, Schedules},
+    entity::Entity,
+    schedule::{IntoScheduleConfigs, Schedule},
     world::World,
 };
 
+use bevy_log::warn;
+
 use crate::{
+    commands::auto_init_state,
+    components::StateRegistration,
     prelude::{
         on_enter_transition, on_exit_transition, on_reenter_transition, on_reexit_transition,
+        on_transition_transition,
     },
-    state::State,
+    state::{State, StateRepr},
     state_scoped::despawn_state_scoped,
-    system_set::{StateSystemSet, StateUpdates},
-    transitions::{on_deinit_transition, on_init_transition},
+    system_set::StateSystemSet,
+    transitions::{on_deinit_transition, on_init_transition, on_initial_transition},
 };
 
 /// State registration configuration.
 /// Allows for configuration of enter/exit state systems like transitions and state scoped entities.
 /// Configuration is only applied when registering state for the first time.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StateConfig {
     state_scoped: bool,
     on_enter: bool,
     on_exit: bool,
     on_reenter: bool,
     on_reexit: bool,
+    on_transition: bool,
     on_init: bool,
     on_deinit: bool,
+    initial_transitions: bool,
+    auto_init: bool,
+    deterministic_order: bool,
+    priority: i32,
 }
 
 impl Default for StateConfig {
@@ -36,41 +50,98 @@ impl Default for StateConfig {
             on_exit: true,
             on_reenter: false,
             on_reexit: false,
+            on_transition: false,
             on_init: true,
             on_deinit: true,
+            initial_transitions: false,
+            auto_init: false,
+            deterministic_order: false,
+            priority: 0,
         }
     }
 }
 
 impl StateConfig {
-    /// Applies the configuration to the world.
-    pub(crate) fn apply<S: State>(self, world: &mut World) {
-        let mut schedules = world.resource_mut::<Schedules>();
-        let schedule = schedules.entry(StateUpdates);
+    /// Adds systems enabled by this configuration to the schedule.
+    /// Added systems are tied to the state registration entity.
+    pub(crate) fn add_systems<S: State>(&self, schedule: &mut Schedule, registration: Entity) {
         if self.state_scoped {
-            schedule.add_systems(despawn_state_scoped::<S>.in_set(StateSystemSet::exit::<S>()));
+            schedule.add_systems(
+                despawn_state_scoped::<S>
+                    .in_set(StateSystemSet::exit::<S>())
+                    .run_if(StateRegistration::is_active(registration)),
+            );
         }
         if self.on_enter {
-            schedule.add_systems(on_enter_transition::<S>.in_set(StateSystemSet::enter::<S>()));
+            schedule.add_systems(
+                on_enter_transition::<S>
+                    .in_set(StateSystemSet::enter::<S>())
+                    .run_if(StateRegistration::is_active(registration)),
+            );
         }
         if self.on_exit {
-            schedule.add_systems(on_exit_transition::<S>.in_set(StateSystemSet::exit::<S>()));
+            schedule.add_systems(
+                on_exit_transition::<S>
+                    .in_set(StateSystemSet::exit::<S>())
+                    .run_if(StateRegistration::is_active(registration)),
+            );
         }
         if self.on_reenter {
-            schedule.add_systems(on_reenter_transition::<S>.in_set(StateSystemSet::enter::<S>()));
+            schedule.add_systems(
+                on_reenter_transition::<S>
+                    .in_set(StateSystemSet::enter::<S>())
+                    .run_if(StateRegistration::is_active(registration)),
+            );
         }
         if self.on_reexit {
-            schedule.add_systems(on_reexit_transition::<S>.in_set(StateSystemSet::exit::<S>()));
+            schedule.add_systems(
+                on_reexit_transition::<S>
+                    .in_set(StateSystemSet::exit::<S>())
+                    .run_if(StateRegistration::is_active(registration)),
+            );
         }
+        if self.on_transition {
+            schedule.add_systems(
+                on_transition_transition::<S>
+                    .in_set(StateSystemSet::transition::<S>())
+                    .run_if(StateRegistration::is_active(registration)),
+            );
+        }
+    }
 
+    /// Applies the configuration to the world.
+    /// Added observers are tied to the state registration entity.
+    pub(crate) fn apply<S: State>(self, world: &mut World, registration: Entity) {
+        let mut observers = vec![];
         if self.on_init {
-            world.add_observer(on_init_transition::<S>);
+            observers.push(world.add_observer(on_init_transition::<S>).id());
         }
         if self.on_deinit {
-            world.add_observer(on_deinit_transition::<S>);
+            observers.push(world.add_observer(on_deinit_transition::<S>).id());
+        }
+        if self.initial_transitions {
+            observers.push(world.add_observer(on_initial_transition::<S>).id());
+        }
+        let mut registration = world.get_mut::<StateRegistration>(registration).unwrap();
+        registration.observers.extend(observers);
+
+        if self.auto_init {
+            registration.auto_init = Some(auto_init_state::<S>);
         }
     }
 
+    /// Rejects options which are not supported by state `S`.
+    pub(crate) fn validate<S: State>(mut self) -> Self {
+        if self.auto_init && <S::Repr as StateRepr>::placeholder().is_none() {
+            warn!(
+                "State {} can't be automatically initialized, only optional states are supported.",
+                disqualified::ShortName::of::<S>()
+            );
+            self.auto_init = false;
+        }
+        self
+    }
+
     /// Config that creates no transitions.
     /// For standard [`OnExit`] and [`OnEnter`] use the [`StateTransitionsConfig::default`].
     pub fn empty() -> Self {
@@ -80,8 +151,13 @@ impl StateConfig {
             on_exit: false,
             on_reenter: false,
             on_reexit: false,
+            on_transition: false,
             on_init: false,
             on_deinit: false,
+            initial_transitions: false,
+            auto_init: false,
+            deterministic_order: false,
+            priority: 0,
         }
     }
 
@@ -115,6 +191,12 @@ impl StateConfig {
         self
     }
 
+    /// Sets whether combined state transition will be enabled.
+    pub fn with_on_transition(mut self, enabled: bool) -> Self {
+        self.on_transition = enabled;
+        self
+    }
+
     /// Sets whether state init transition will be enabled.
     pub fn with_on_init(mut self, enabled: bool) -> Self {
         self.on_init = enabled;
@@ -126,4 +208,72 @@ impl StateConfig {
         self.on_deinit = enabled;
         self
     }
+
+    /// Sets whether initial state values will trigger enter transitions during next state update.
+    /// This applies to states added at startup as well as at runtime, both global and local.
+    /// Initial transitions have no previous value, so exit transitions are not triggered.
+    /// Updates requested before the initial transition are applied during the following state update.
+    pub fn with_initial_transitions(mut self, enabled: bool) -> Self {
+        self.initial_transitions = enabled;
+        self
+    }
+
+    /// Sets whether this state will run in a deterministic order relative to other states
+    /// with the same [`State::ORDER`], which also have deterministic order enabled.
+    /// Ties are broken by [`Self::with_priority`], then by registration order.
+    /// This applies to updates, exits, transitions and enters; exits run in reverse order.
+    pub fn with_deterministic_order(mut self, enabled: bool) -> Self {
+        self.deterministic_order = enabled;
+        self
+    }
+
+    /// Sets priority of this state, used by [`Self::with_deterministic_order`].
+    /// States with higher priority run first.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Key used to order states with deterministic order, lower keys run first.
+    fn order_key(&self, registration: &StateRegistration) -> (Reverse<i32>, u32) {
+        (Reverse(self.priority), registration.sequence())
+    }
+
+    /// Sets whether state will be initialized automatically, once all of its dependencies
+    /// were initialized on an entity through [`CoreStatesExt::init_state`](crate::commands::CoreStatesExt::init_state).
+    /// The initial value is computed by running [`State::update`] once,
+    /// or the [`StateUpdateOverride`](crate::components::StateUpdateOverride) if the entity has one.
+    ///
+    /// Only optional states are supported, since the update starts from a `None` current value.
+    /// For non-optional states this option is rejected with a warning during registration.
+    pub fn with_auto_init(mut self, enabled: bool) -> Self {
+        self.auto_init = enabled;
+        self
+    }
+}
+
+/// Orders state `this` relative to `others` with the same [`State::ORDER`],
+/// if both have deterministic order enabled.
+pub(crate) fn add_tie_breaks<'a>(
+    schedule: &mut Schedule,
+    this: &StateRegistration,
+    others: impl IntoIterator<Item = &'a StateRegistration>,
+) {
+    if !this.config().deterministic_order {
+        return;
+    }
+    let key = this.config().order_key(this);
+    for other in others {
+        if other.type_id() == this.type_id()
+            || other.order() != this.order()
+            || !other.config().deterministic_order
+        {
+            continue;
+        }
+        if other.config().order_key(other) < key {
+            schedule.configure_sets(StateSystemSet::tie_break(other.type_id(), this.type_id()));
+        } else {
+            schedule.configure_sets(StateSystemSet::tie_break(this.type_id(), other.type_id()));
+        }
+    }
 }
@@ -0,0 +1,354 @@
+//! Delayed state updates driven by [`Time`].
+//!
+//! Useful for switching states after a fixed duration, like splash screens, respawn delays or buff durations,
+//! without writing a timer system for each of them.
+//! A delayed update is stored on the state entity and requested during [`StateUpdates`](crate::system_set::StateUpdates)
+//! once the elapsed [`Time`] reaches its deadline.
+//! Requesting another update through [`CoreStatesExt::update_state`](crate::commands::CoreStatesExt::update_state)
+//! or any of the update backends cancels the delayed update.
+//! Removing the state also removes delayed updates of the state and its dependents.
+
+use core::time::Duration;
+
+use bevy_ecs::{
+    component::Component,
+    prelude::{Command, Commands, Entity, Result, World},
+    system::{Populated, Res},
+};
+use bevy_time::Time;
+
+use crate::{
+    commands::{IntoStateUpdate, state_target_entity},
+    components::StateData,
+    error::{StateError, warn_error},
+    state::{State, StateUpdate},
+};
+
+/// Component storing a state update which will be requested once the deadline is reached.
+#[derive(Component)]
+pub struct DelayedUpdate<S: State> {
+    /// Elapsed [`Time`] at which the update is requested.
+    deadline: Duration,
+    /// The update to request.
+    update: S::Update,
+}
+
+impl<S: State> DelayedUpdate<S> {
+    /// Returns the elapsed [`Time`] at which the update is requested.
+    pub fn deadline(&self) -> Duration {
+        self.deadline
+    }
+
+    /// Reference to the delayed update.
+    pub fn update(&self) -> &S::Update {
+        &self.update
+    }
+}
+
+/// System that requests delayed updates once their deadline is reached.
+pub fn apply_delayed_updates<S: State>(
+    mut commands: Commands,
+    time: Option<Res<Time>>,
+    mut query: Populated<(Entity, &mut StateData<S>, &mut DelayedUpdate<S>)>,
+) {
+    let Some(time) = time else {
+        return;
+    };
+    let now = time.elapsed();
+    for (entity, mut state, mut delayed) in query.iter_mut() {
+        if delayed.deadline > now {
+            continue;
+        }
+        let update = core::mem::take(&mut delayed.update);
+        state.update.merge(update);
+        commands.entity(entity).remove::<DelayedUpdate<S>>();
+    }
+}
+
+struct DelayStateUpdateCommand<S: IntoStateUpdate> {
+    local: Option<Entity>,
+    update: S::Update,
+    delay: Duration,
+}
+
+impl<S: IntoStateUpdate> DelayStateUpdateCommand<S> {
+    fn new(local: Option<Entity>, update: S, delay: Duration) -> Self {
+        Self {
+            local,
+            update: update.into_state_update(),
+            delay,
+        }
+    }
+}
+
+impl<S: IntoStateUpdate> Command<Result> for DelayStateUpdateCommand<S> {
+    fn apply(self, world: &mut World) -> Result {
+        let entity = state_target_entity(world, self.local)?;
+        let now = world
+            .get_resource::<Time>()
+            .map(Time::elapsed)
+            .unwrap_or_default();
+        let Ok(mut entity) = world.get_entity_mut(entity) else {
+            return Err(StateError::NoSuchEntity(entity).into());
+        };
+        if !entity.contains::<StateData<S>>() {
+            return Err(StateError::missing_state::<S>(entity.id()).into());
+        }
+        entity.insert(DelayedUpdate::<S> {
+            deadline: now + self.delay,
+            update: self.update,
+        });
+        Ok(())
+    }
+}
+
+struct CancelDelayedUpdateCommand<S: State> {
+    local: Option<Entity>,
+    _state: core::marker::PhantomData<S>,
+}
+
+impl<S: State> Command<Result> for CancelDelayedUpdateCommand<S> {
+    fn apply(self, world: &mut World) -> Result {
+        let entity = state_target_entity(world, self.local)?;
+        let Ok(mut entity) = world.get_entity_mut(entity) else {
+            return Err(StateError::NoSuchEntity(entity).into());
+        };
+        entity.remove::<DelayedUpdate<S>>();
+        Ok(())
+    }
+}
+
+/// Methods for requesting delayed state updates.
+/// Only one delayed update can be pending per state, a new request overwrites the pending one.
+///
+/// Depending on which medium this is called on, those methods will have:
+/// - immediate effect: [`World`], [`SubApp`](bevy_app::SubApp) and [`App`](bevy_app::App),
+/// - deferred effect: [`Commands`].
+pub trait DelayedStatesExt {
+    /// Requests a state update once `delay` of [`Time`] has elapsed.
+    fn update_state_after<S: IntoStateUpdate>(
+        &mut self,
+        local: Option<Entity>,
+        update: S,
+        delay: Duration,
+    ) -> &mut Self;
+
+    /// Cancels the pending delayed update.
+    fn cancel_delayed_update<S: State>(&mut self, local: Option<Entity>) -> &mut Self;
+}
+
+impl DelayedStatesExt for Commands<'_, '_> {
+    fn update_state_after<S: IntoStateUpdate>(
+        &mut self,
+        local: Option<Entity>,
+        update: S,
+        delay: Duration,
+    ) -> &mut Self {
+        self.queue(DelayStateUpdateCommand::new(local, update, delay));
+        self
+    }
+
+    fn cancel_delayed_update<S: State>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.queue(CancelDelayedUpdateCommand::<S> {
+            local,
+            _state: Default::default(),
+        });
+        self
+    }
+}
+
+impl DelayedStatesExt for World {
+    fn update_state_after<S: IntoStateUpdate>(
+        &mut self,
+        local: Option<Entity>,
+        update: S,
+        delay: Duration,
+    ) -> &mut Self {
+        DelayStateUpdateCommand::new(local, update, delay)
+            .apply(self)
+            .unwrap_or_else(warn_error);
+        self
+    }
+
+    fn cancel_delayed_update<S: State>(&mut self, local: Option<Entity>) -> &mut Self {
+        CancelDelayedUpdateCommand::<S> {
+            local,
+            _state: Default::default(),
+        }
+        .apply(self)
+        .unwrap_or_else(warn_error);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl DelayedStatesExt for bevy_app::SubApp {
+    fn update_state_after<S: IntoStateUpdate>(
+        &mut self,
+        local: Option<Entity>,
+        update: S,
+        delay: Duration,
+    ) -> &mut Self {
+        self.world_mut().update_state_after(local, update, delay);
+        self
+    }
+
+    fn cancel_delayed_update<S: State>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.world_mut().cancel_delayed_update::<S>(local);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl DelayedStatesExt for bevy_app::App {
+    fn update_state_after<S: IntoStateUpdate>(
+        &mut self,
+        local: Option<Entity>,
+        update: S,
+        delay: Duration,
+    ) -> &mut Self {
+        self.main_mut().update_state_after(local, update, delay);
+        self
+    }
+
+    fn cancel_delayed_update<S: State>(&mut self, local: Option<Entity>) -> &mut Self {
+        self.main_mut().cancel_delayed_update::<S>(local);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use bevy_ecs::{schedule::Schedules, world::World};
+    use bevy_state_macros::State;
+    use bevy_time::Time;
+
+    use super::{DelayedStatesExt, DelayedUpdate};
+    use crate::{
+        self as bevy_state_v3,
+        backends::history::{HistoryStatesExt, HistoryUpdate},
+        commands::CoreStatesExt,
+        components::StateData,
+        config::StateConfig,
+        state::State,
+        state_set::StateSetData,
+        system_set::StateUpdates,
+    };
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Splash {
+        Logo,
+        Menu,
+        Credits,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Tool {
+        Brush,
+        Eraser,
+    }
+
+    impl State for Tool {
+        type Dependencies = ();
+        type Update = HistoryUpdate<Self>;
+        type Repr = Self;
+
+        fn update(
+            state: &mut StateData<Self>,
+            _: StateSetData<'_, Self::Dependencies>,
+        ) -> Self::Repr {
+            state.apply_history_op()
+        }
+    }
+
+    #[derive(State, Default, Clone, Debug, PartialEq)]
+    #[dependency(Splash = Splash::Menu)]
+    enum MenuPage {
+        #[default]
+        Main,
+        Settings,
+    }
+
+    fn current(world: &mut World) -> Splash {
+        world
+            .query::<&StateData<Splash>>()
+            .single(world)
+            .unwrap()
+            .current()
+            .clone()
+    }
+
+    #[test]
+    fn delayed_local_update() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<Time>();
+        world.register_state::<Splash>(StateConfig::empty());
+        let local = Some(world.spawn_empty().id());
+        world.init_state(local, Splash::Logo);
+
+        world.update_state_after(local, Splash::Menu, Duration::from_secs(2));
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), Splash::Logo);
+
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), Splash::Menu);
+
+        world.update_state_after(local, Splash::Credits, Duration::from_secs(1));
+        world.update_state(local, Splash::Logo);
+        world.run_schedule(StateUpdates);
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        world.run_schedule(StateUpdates);
+        assert_eq!(current(&mut world), Splash::Logo);
+    }
+
+    #[test]
+    fn backend_update_cancels_delay() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<Time>();
+        world.register_state::<Tool>(StateConfig::empty());
+        world.init_state(None, Tool::Brush);
+        world.update_state(None, Tool::Eraser);
+        world.run_schedule(StateUpdates);
+
+        world.update_state_after(None, Tool::Eraser, Duration::from_secs(1));
+        world.undo_state::<Tool>(None);
+        world.run_schedule(StateUpdates);
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        world.run_schedule(StateUpdates);
+        let state = world.query::<&StateData<Tool>>().single(&world).unwrap();
+        assert_eq!(state.current(), &Tool::Brush);
+    }
+
+    #[test]
+    fn remove_state_removes_dependent_delays() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<Time>();
+        world.register_state::<Splash>(StateConfig::empty());
+        world.register_state::<MenuPage>(StateConfig::empty());
+        let local = world.spawn_empty().id();
+        world.init_state(Some(local), Splash::Menu);
+        world.init_state(Some(local), Some(MenuPage::Main));
+
+        world.update_state_after(Some(local), Splash::Credits, Duration::from_secs(1));
+        world.update_state_after(Some(local), MenuPage::Settings, Duration::from_secs(1));
+        world.remove_state::<Splash>(Some(local));
+        let entity = world.entity(local);
+        assert!(!entity.contains::<DelayedUpdate<Splash>>());
+        assert!(!entity.contains::<DelayedUpdate<MenuPage>>());
+    }
+}
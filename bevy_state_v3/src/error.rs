@@ -0,0 +1,153 @@
+//! Errors returned by state commands.
+
+use core::fmt::{Display, Formatter};
+
+use bevy_ecs::{entity::Entity, error::BevyError};
+use bevy_log::warn;
+use disqualified::ShortName;
+
+use crate::state::State;
+
+/// Error returned by state commands.
+/// Commands report it through Bevy's error handler, while [`World`](bevy_ecs::world::World)
+/// methods log it as a warning, unless called through [`TryStatesExt`](crate::commands::TryStatesExt).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// Global state was targeted, but no entity has the [`GlobalMarker`](crate::util::GlobalMarker).
+    NoGlobalEntity,
+    /// Global state was targeted, but multiple entities have the [`GlobalMarker`](crate::util::GlobalMarker).
+    MultipleGlobalEntities,
+    /// Targeted local entity does not exist.
+    NoSuchEntity(Entity),
+    /// Targeted entity does not have the state.
+    MissingState {
+        /// Type name of the state.
+        state: &'static str,
+        /// Targeted entity.
+        entity: Entity,
+    },
+    /// Targeted entity already has the state.
+    AlreadyInitialized {
+        /// Type name of the state.
+        state: &'static str,
+        /// Targeted entity.
+        entity: Entity,
+    },
+    /// State machinery is already registered.
+    AlreadyRegistered {
+        /// Type name of the state.
+        state: &'static str,
+    },
+    /// State machinery is not registered.
+    NotRegistered {
+        /// Type name of the state.
+        state: &'static str,
+    },
+    /// Dependencies of the state are not registered.
+    UnregisteredDependencies {
+        /// Type name of the state.
+        state: &'static str,
+        /// Type names of unregistered dependencies.
+        missing: Vec<&'static str>,
+    },
+    /// Dependencies of the state are not present on the targeted entity.
+    MissingDependencies {
+        /// Type name of the state.
+        state: &'static str,
+        /// Targeted entity.
+        entity: Entity,
+        /// Chains of missing states, from the initialized state to the root-most missing dependency.
+        chains: Vec<Vec<String>>,
+    },
+}
+
+impl StateError {
+    /// Error for a state missing from the entity.
+    pub fn missing_state<S: State>(entity: Entity) -> Self {
+        Self::MissingState {
+            state: core::any::type_name::<S>(),
+            entity,
+        }
+    }
+
+    /// Error for a state that is already present on the entity.
+    pub fn already_initialized<S: State>(entity: Entity) -> Self {
+        Self::AlreadyInitialized {
+            state: core::any::type_name::<S>(),
+            entity,
+        }
+    }
+
+    /// Error for a state which is already registered.
+    pub fn already_registered<S: State>() -> Self {
+        Self::AlreadyRegistered {
+            state: core::any::type_name::<S>(),
+        }
+    }
+
+    /// Error for a state which is not registered.
+    pub fn not_registered<S: State>() -> Self {
+        Self::NotRegistered {
+            state: core::any::type_name::<S>(),
+        }
+    }
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NoGlobalEntity => write!(f, "No global state entity exists."),
+            Self::MultipleGlobalEntities => write!(f, "Multiple global state entities exist."),
+            Self::NoSuchEntity(entity) => write!(f, "Entity {entity} does not exist."),
+            Self::MissingState { state, entity } => write!(
+                f,
+                "Entity {entity} does not have state {}.",
+                ShortName(state)
+            ),
+            Self::AlreadyInitialized { state, entity } => {
+                write!(f, "Entity {entity} already has state {}.", ShortName(state))
+            }
+            Self::AlreadyRegistered { state } => {
+                write!(f, "State {} is already registered.", ShortName(state))
+            }
+            Self::NotRegistered { state } => {
+                write!(f, "State {} is not registered.", ShortName(state))
+            }
+            Self::UnregisteredDependencies { state, missing } => {
+                let missing = missing
+                    .iter()
+                    .map(|name| ShortName(name).to_string())
+                    .collect::<Vec<_>>();
+                write!(
+                    f,
+                    "State {} depends on unregistered states: {}.",
+                    ShortName(state),
+                    missing.join(", ")
+                )
+            }
+            Self::MissingDependencies {
+                state,
+                entity,
+                chains,
+            } => {
+                let chains = chains
+                    .iter()
+                    .map(|chain| chain.join(" -> "))
+                    .collect::<Vec<_>>();
+                write!(
+                    f,
+                    "Entity {entity} is missing dependencies of state {}: {}.",
+                    ShortName(state),
+                    chains.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for StateError {}
+
+/// Logs command errors when commands are applied directly to the world.
+pub(crate) fn warn_error(error: BevyError) {
+    warn!("{error}");
+}
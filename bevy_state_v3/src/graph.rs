@@ -0,0 +1,306 @@
+//! Introspection of registered states and their dependencies.
+//!
+//! The graph is a snapshot of state registrations, so it has to be rebuilt to reflect later changes.
+//! ```rs
+//! let graph = StateGraph::from_world(app.world_mut());
+//! std::fs::write("states.dot", graph.to_dot()).unwrap();
+//! ```
+//! States are identified by their full type names, short names are only used as labels.
+
+use core::fmt::Write;
+
+use bevy_ecs::{
+    resource::Resource,
+    world::{FromWorld, World},
+};
+use disqualified::ShortName;
+
+use crate::{components::StateRegistration, config::StateConfig, state::State};
+
+/// Registered state in the [`StateGraph`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateNode {
+    /// Type name of the state, which identifies it in the graph.
+    pub type_name: &'static str,
+    /// Short name of the state type, used as the label.
+    pub name: String,
+    /// Type names of direct dependencies of the state.
+    pub dependencies: Vec<&'static str>,
+    /// Type names of states on other entities, which the state is synchronized from.
+    /// Only proxy states from [`remote`](crate::remote) have those.
+    pub remote_dependencies: Vec<&'static str>,
+    /// Update order of the state.
+    pub order: u32,
+    /// Configuration the state was registered with.
+    pub config: StateConfig,
+}
+
+/// Dependency graph of registered states.
+/// States are sorted by their update order, then by name.
+///
+/// Can be inserted as a resource with [`World::init_resource`],
+/// but it won't be updated when states are registered or unregistered.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct StateGraph {
+    nodes: Vec<StateNode>,
+}
+
+/// Takes a snapshot of currently registered states,
+/// states registered or unregistered afterwards are not reflected in the graph.
+impl FromWorld for StateGraph {
+    fn from_world(world: &mut World) -> Self {
+        let mut nodes = world
+            .query::<&StateRegistration>()
+            .iter(world)
+            .map(|registration| StateNode {
+                type_name: registration.type_name(),
+                name: registration.name().to_string(),
+                dependencies: registration.dependency_type_names().to_vec(),
+                remote_dependencies: registration.remote_dependency_type_names().to_vec(),
+                order: registration.order(),
+                config: registration.config().clone(),
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_by(|a, b| {
+            a.order
+                .cmp(&b.order)
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.type_name.cmp(b.type_name))
+        });
+        Self { nodes }
+    }
+}
+
+impl StateGraph {
+    /// Returns all registered states.
+    pub fn nodes(&self) -> &[StateNode] {
+        &self.nodes
+    }
+
+    /// Returns state `S`, if it's registered.
+    pub fn node<S: State>(&self) -> Option<&StateNode> {
+        let type_name = core::any::type_name::<S>();
+        self.nodes.iter().find(|node| node.type_name == type_name)
+    }
+
+    /// Returns registered states which directly depend on state `S`, including its proxy states.
+    pub fn dependents<S: State>(&self) -> impl Iterator<Item = &StateNode> {
+        let type_name = core::any::type_name::<S>();
+        self.nodes.iter().filter(move |node| {
+            node.dependencies.contains(&type_name) || node.remote_dependencies.contains(&type_name)
+        })
+    }
+
+    /// Returns type names of all states, including unregistered dependencies.
+    fn type_names(&self) -> Vec<&'static str> {
+        let mut type_names = self
+            .nodes
+            .iter()
+            .map(|node| node.type_name)
+            .collect::<Vec<_>>();
+        let dependencies = self
+            .nodes
+            .iter()
+            .flat_map(|node| node.dependencies.iter().chain(&node.remote_dependencies));
+        for dependency in dependencies {
+            if !type_names.contains(dependency) {
+                type_names.push(dependency);
+            }
+        }
+        type_names
+    }
+
+    /// Returns the label of a state, which is the short name of its type.
+    fn label(&self, type_name: &str) -> String {
+        match self.nodes.iter().find(|node| node.type_name == type_name) {
+            Some(node) => node.name.clone(),
+            None => ShortName(type_name).to_string(),
+        }
+    }
+
+    /// Renders the graph in Graphviz DOT format.
+    /// Edges point from dependencies to their dependents, edges from remote dependencies are dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph States {\n");
+        for type_name in self.type_names() {
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\"];",
+                escape_dot(type_name),
+                escape_dot(&self.label(type_name))
+            );
+        }
+        for node in &self.nodes {
+            for dependency in &node.dependencies {
+                let _ = writeln!(
+                    dot,
+                    "    \"{}\" -> \"{}\";",
+                    escape_dot(dependency),
+                    escape_dot(node.type_name)
+                );
+            }
+            for dependency in &node.remote_dependencies {
+                let _ = writeln!(
+                    dot,
+                    "    \"{}\" -> \"{}\" [style=dashed];",
+                    escape_dot(dependency),
+                    escape_dot(node.type_name)
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the graph as a Mermaid flowchart.
+    /// Edges point from dependencies to their dependents, edges from remote dependencies are dotted.
+    pub fn to_mermaid(&self) -> String {
+        let type_names = self.type_names();
+        let id = |type_name: &str| {
+            type_names
+                .iter()
+                .position(|other| *other == type_name)
+                .unwrap()
+        };
+        let mut mermaid = String::from("flowchart TD\n");
+        for (i, type_name) in type_names.iter().enumerate() {
+            let _ = writeln!(
+                mermaid,
+                "    s{i}[\"{}\"]",
+                escape_mermaid(&self.label(type_name))
+            );
+        }
+        for node in &self.nodes {
+            for dependency in &node.dependencies {
+                let _ = writeln!(
+                    mermaid,
+                    "    s{} --> s{}",
+                    id(dependency),
+                    id(node.type_name)
+                );
+            }
+            for dependency in &node.remote_dependencies {
+                let _ = writeln!(
+                    mermaid,
+                    "    s{} -.-> s{}",
+                    id(dependency),
+                    id(node.type_name)
+                );
+            }
+        }
+        mermaid
+    }
+}
+
+fn escape_dot(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(name: &str) -> String {
+    name.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use core::any::type_name;
+
+    use bevy_ecs::{
+        schedule::Schedules,
+        world::{FromWorld, World},
+    };
+    use bevy_state_macros::State;
+
+    use crate::{
+        self as bevy_state_v3, commands::CoreStatesExt, config::StateConfig, graph::StateGraph,
+    };
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Game {
+        Menu,
+        Playing,
+    }
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Paused {
+        Yes,
+        No,
+    }
+
+    #[derive(State, Default, Clone, Debug, PartialEq)]
+    #[dependency(all(Game = Game::Playing, Paused = Paused::No))]
+    enum Combat {
+        #[default]
+        Idle,
+    }
+
+    mod editor {
+        use bevy_state_macros::State;
+
+        use crate::{self as bevy_state_v3};
+
+        #[derive(State, Clone, Debug, PartialEq)]
+        pub enum Game {
+            Editing,
+        }
+    }
+
+    #[test]
+    fn state_graph() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Paused>(StateConfig::empty());
+        world.register_state::<Game>(StateConfig::default());
+        world.register_state::<Combat>(StateConfig::empty().with_on_enter(true));
+        world.init_state(None, Game::Menu);
+        world.init_state(None, Paused::Yes);
+        world.update_state(None, Game::Playing);
+        world.update_state(None, Paused::No);
+
+        let graph = StateGraph::from_world(&mut world);
+        let names = graph.nodes().iter().map(|node| node.name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), vec!["Game", "Paused", "Combat"]);
+        let combat = graph.node::<Combat>().unwrap();
+        assert_eq!(
+            combat.dependencies,
+            vec![type_name::<Game>(), type_name::<Paused>()]
+        );
+        assert_eq!(combat.config, StateConfig::empty().with_on_enter(true));
+        let dependents = graph.dependents::<Game>().map(|node| node.name.as_str());
+        assert_eq!(dependents.collect::<Vec<_>>(), vec!["Combat"]);
+
+        let (game, paused, combat) = (
+            type_name::<Game>(),
+            type_name::<Paused>(),
+            type_name::<Combat>(),
+        );
+        assert_eq!(
+            graph.to_dot(),
+            format!(
+                "digraph States {{\n    \"{game}\" [label=\"Game\"];\n    \"{paused}\" [label=\"Paused\"];\n    \"{combat}\" [label=\"Combat\"];\n    \"{game}\" -> \"{combat}\";\n    \"{paused}\" -> \"{combat}\";\n}}\n"
+            )
+        );
+        assert_eq!(
+            graph.to_mermaid(),
+            "flowchart TD\n    s0[\"Game\"]\n    s1[\"Paused\"]\n    s2[\"Combat\"]\n    s0 --> s2\n    s1 --> s2\n"
+        );
+    }
+
+    #[test]
+    fn same_short_names() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Game>(StateConfig::empty());
+        world.register_state::<editor::Game>(StateConfig::default());
+        world.init_state(None, editor::Game::Editing);
+
+        let graph = StateGraph::from_world(&mut world);
+        assert_eq!(graph.nodes().len(), 2);
+        assert_eq!(graph.node::<Game>().unwrap().config, StateConfig::empty());
+        assert_eq!(
+            graph.node::<editor::Game>().unwrap().config,
+            StateConfig::default()
+        );
+    }
+}
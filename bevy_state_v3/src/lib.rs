@@ -4,33 +4,70 @@
 
 #[cfg(feature = "bevy_app")]
 pub mod app;
+pub mod backends;
 pub mod commands;
 pub mod components;
 pub mod config;
+#[cfg(feature = "bevy_time")]
+pub mod delayed;
+pub mod error;
+pub mod graph;
+pub mod observers;
+pub mod query;
+pub mod remote;
+// Tests of this module run with `--features schedules` or `--all-features`.
+#[cfg(feature = "schedules")]
+pub mod schedules;
 pub mod state;
 pub mod state_scoped;
 pub mod state_set;
 pub mod system_set;
+pub mod transition_log;
 pub mod transitions;
 pub mod util;
+pub mod validation;
 
 /// Re-export of common state types and functions.
 pub mod prelude {
     #[cfg(feature = "bevy_app")]
     pub use crate::app::StatePlugin;
-    pub use crate::commands::{CoreStatesExt, IntoStateUpdate};
-    pub use crate::components::StateData;
+    pub use crate::backends::{
+        history::{HistoryStatesExt, HistoryUpdate},
+        queue::{QueueStatesExt, QueueUpdate},
+        retained::{RetainedStatesExt, RetainedUpdate},
+        shift::{ShiftEdge, ShiftStatesExt, ShiftUpdate, Variants},
+        stack::{StackStatesExt, StackUpdate},
+    };
+    pub use crate::commands::{CoreStatesExt, IntoStateUpdate, TryStatesExt};
+    pub use crate::components::{StateData, StateUpdateOverride};
     pub use crate::config::StateConfig;
+    #[cfg(feature = "bevy_time")]
+    pub use crate::delayed::DelayedStatesExt;
+    pub use crate::error::StateError;
+    pub use crate::graph::StateGraph;
+    pub use crate::observers::{
+        StateObserversExt, TransitionEvent, on_enter, on_exit, on_reenter, on_reexit,
+    };
+    pub use crate::query::StateQuery;
+    pub use crate::remote::{GlobalState, RelatedState};
+    #[cfg(feature = "schedules")]
+    pub use crate::schedules::StateSchedulesExt;
     pub use crate::state::{State, StateRepr, StateUpdate};
     pub use crate::state_scoped::{StateScoped, despawn_state_scoped};
     pub use crate::state_set::{StateSet, StateSetData};
+    pub use crate::transition_log::StateTransitionLog;
     pub use crate::transitions::{
-        OnEnter, OnExit, OnInit, OnReenter, OnReexit, on_enter_transition, on_exit_transition,
-        on_reenter_transition, on_reexit_transition,
+        OnEnter, OnExit, OnInit, OnReenter, OnReexit, OnTransition, OnTransitionRejected,
+        on_enter_transition, on_exit_transition, on_reenter_transition, on_reexit_transition,
+        on_transition_transition,
+    };
+    pub use crate::util::{
+        Global, in_any_state, in_state, local_in_any_state, local_state_exists,
+        local_state_exited_from, local_state_is_some, local_state_reentered, state_changed,
+        state_changed_to, state_exists, state_exited_from, state_is_some, state_reentered,
     };
-    pub use crate::util::{Global, in_state, state_changed, state_changed_to};
 
-    pub use bevy_state_macros::State;
+    pub use bevy_state_macros::{State, Variants};
 }
 
 #[cfg(test)]
@@ -38,8 +75,8 @@ mod tests {
     use std::{any::type_name, fmt::Debug};
 
     use bevy_ecs::{
-        entity::Entity, event::Event, observer::On, resource::Resource, schedule::Schedules,
-        system::ResMut, world::World,
+        entity::Entity, event::Event, observer::On, query::With, resource::Resource,
+        schedule::Schedules, system::ResMut, world::World,
     };
     use bevy_state_macros::State;
 
@@ -49,9 +86,14 @@ mod tests {
         prelude::{OnInit, StateScoped},
         state_set::StateSetData,
         system_set::StateUpdates,
-        transitions::{OnDeinit, OnEnter, OnExit},
+        transitions::{OnDeinit, OnEnter, OnExit, OnTransition, OnTransitionRejected},
+    };
+    use crate::{
+        commands::{CoreStatesExt, TryStatesExt},
+        components::{RegisteredState, StateData, StateRegistration, StateUpdateOverride},
+        error::StateError,
+        state::State,
     };
-    use crate::{commands::CoreStatesExt, components::StateData, state::State};
 
     #[derive(State, Default, Clone, Debug, PartialEq)]
     enum ManualState {
@@ -231,6 +273,138 @@ mod tests {
         assert!(transitions[6..=7].contains(&type_name::<OnEnter<ComputedState>>()));
     }
 
+    #[test]
+    fn deterministic_order() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        let config = StateConfig::default().with_deterministic_order(true);
+        world.register_state::<ManualState>(config.clone());
+        world.register_state::<ManualState2>(config.clone().with_priority(1));
+        world.register_state::<SubState2>(config.clone());
+        world.register_state::<ComputedState>(config);
+        world.init_state(None, ManualState::A);
+        world.init_state(None, ManualState2::C);
+        world.init_state(None, None::<SubState2>);
+        world.init_state(None, None::<ComputedState>);
+        world.update_state(None, ManualState::A);
+        world.run_schedule(StateUpdates);
+
+        world.init_resource::<StateTransitionTracker>();
+        world.add_observer(track::<OnExit<ManualState>>());
+        world.add_observer(track::<OnEnter<ManualState>>());
+        world.add_observer(track::<OnExit<ManualState2>>());
+        world.add_observer(track::<OnEnter<ManualState2>>());
+        world.add_observer(track::<OnExit<SubState2>>());
+        world.add_observer(track::<OnEnter<SubState2>>());
+        world.add_observer(track::<OnExit<ComputedState>>());
+        world.add_observer(track::<OnEnter<ComputedState>>());
+        world.update_state(None, ManualState::B);
+        world.update_state(None, ManualState2::D);
+        world.run_schedule(StateUpdates);
+
+        // Priority first, then registration order, exits in reverse.
+        assert_eq!(
+            world.resource::<StateTransitionTracker>().0,
+            vec![
+                type_name::<OnExit<ComputedState>>(),
+                type_name::<OnExit<SubState2>>(),
+                type_name::<OnExit<ManualState>>(),
+                type_name::<OnExit<ManualState2>>(),
+                type_name::<OnEnter<ManualState2>>(),
+                type_name::<OnEnter<ManualState>>(),
+                type_name::<OnEnter<SubState2>>(),
+                type_name::<OnEnter<ComputedState>>(),
+            ]
+        );
+    }
+
+    #[derive(State, Clone, Debug, Default, PartialEq)]
+    #[dependency(all(ManualState = ManualState::B, ManualState2 = ManualState2::D))]
+    enum DerivedSubState2 {
+        #[default]
+        X,
+        Y,
+    }
+
+    #[derive(State, Clone, Debug, Default, PartialEq)]
+    #[dependency(any(ManualState = ManualState::A, not(ManualState2 = ManualState2::C)))]
+    struct AnySubState;
+
+    #[test]
+    fn multiple_dependencies() {
+        let world = &mut World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<ManualState>(StateConfig::empty());
+        world.register_state::<ManualState2>(StateConfig::empty());
+        world.register_state::<DerivedSubState2>(StateConfig::empty());
+        world.register_state::<AnySubState>(StateConfig::empty());
+        world.init_state(None, ManualState::B);
+        world.init_state(None, ManualState2::C);
+        world.init_state(None, None::<DerivedSubState2>);
+        world.init_state(None, None::<AnySubState>);
+        world.update_state(None, ManualState::B);
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (DerivedSubState2, None), (AnySubState, None));
+
+        world.update_state(None, ManualState2::D);
+        world.run_schedule(StateUpdates);
+        assert_states!(
+            world,
+            (DerivedSubState2, Some(DerivedSubState2::X)),
+            (AnySubState, Some(AnySubState)),
+        );
+
+        world.update_state(None, DerivedSubState2::Y);
+        world.update_state(None, ManualState::A);
+        world.run_schedule(StateUpdates);
+        assert_states!(
+            world,
+            (DerivedSubState2, None),
+            (AnySubState, Some(AnySubState)),
+        );
+    }
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    #[computed(from = (ManualState, ManualState2), with = compute_both)]
+    struct DerivedComputedState;
+
+    fn compute_both(manual: &ManualState, manual2: &ManualState2) -> Option<DerivedComputedState> {
+        (*manual == ManualState::B && *manual2 == ManualState2::D).then_some(DerivedComputedState)
+    }
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    #[computed(from = ManualState, with = compute_is_a, non_optional)]
+    struct IsA(bool);
+
+    fn compute_is_a(manual: &ManualState) -> IsA {
+        IsA(*manual == ManualState::A)
+    }
+
+    #[test]
+    fn computed_derive() {
+        let world = &mut World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<ManualState>(StateConfig::empty());
+        world.register_state::<ManualState2>(StateConfig::empty());
+        world.register_state::<DerivedComputedState>(StateConfig::empty());
+        world.register_state::<IsA>(StateConfig::empty());
+        world.init_state(None, ManualState::A);
+        world.init_state(None, ManualState2::D);
+        world.init_state(None, None::<DerivedComputedState>);
+        world.init_state(None, IsA(false));
+        world.update_state(None, ManualState::A);
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (DerivedComputedState, None), (IsA, IsA(true)));
+
+        world.update_state(None, ManualState::B);
+        world.run_schedule(StateUpdates);
+        assert_states!(
+            world,
+            (DerivedComputedState, Some(DerivedComputedState)),
+            (IsA, IsA(false))
+        );
+    }
+
     #[test]
     fn state_scoped_entities() {
         let mut world = World::new();
@@ -262,6 +436,502 @@ mod tests {
         assert_eq!(transitions[1], type_name::<OnDeinit<ManualState>>());
     }
 
+    #[test]
+    fn remove_state() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<StateTransitionTracker>();
+        world.register_state::<ManualState>(StateConfig::default());
+        world.register_state::<ComputedState>(StateConfig::default());
+        world.register_state::<SubState>(StateConfig::default());
+        world.add_observer(track::<OnDeinit<ManualState>>());
+        world.add_observer(track::<OnDeinit<ComputedState>>());
+        world.add_observer(track::<OnDeinit<SubState>>());
+        let entity = world.spawn_empty().id();
+        world.init_state(Some(entity), ManualState::A);
+        world.init_state(Some(entity), None::<ComputedState>);
+        world.init_state(Some(entity), None::<SubState>);
+
+        world.remove_state::<ManualState>(Some(entity));
+
+        let entity = world.entity(entity);
+        assert!(!entity.contains::<StateData<ManualState>>());
+        assert!(!entity.contains::<StateData<ComputedState>>());
+        assert!(!entity.contains::<StateData<SubState>>());
+        let transitions = &world.resource::<StateTransitionTracker>().0;
+        assert!(transitions[0..=1].contains(&type_name::<OnDeinit<ComputedState>>()));
+        assert!(transitions[0..=1].contains(&type_name::<OnDeinit<SubState>>()));
+        assert_eq!(transitions[2], type_name::<OnDeinit<ManualState>>());
+    }
+
+    #[test]
+    fn reset_state() {
+        let world = &mut World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<ManualState>(StateConfig::empty());
+        world.register_state::<SubState>(StateConfig::empty());
+        world.init_state(None, ManualState::A);
+        world.init_state(None, None::<SubState>);
+        world.update_state(None, ManualState::B);
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (SubState, Some(SubState::X)));
+
+        world.reset_state::<ManualState>(None);
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (ManualState, ManualState::A), (SubState, None));
+    }
+
+    #[test]
+    fn reset_substate() {
+        let world = &mut World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<ManualState>(StateConfig::empty());
+        world.register_state::<SubState>(StateConfig::empty());
+        world.init_state(None, ManualState::B);
+        world.init_state(None, None::<SubState>);
+        world.update_state(None, SubState::Y);
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (SubState, Some(SubState::Y)));
+
+        // Initial value is not allowed while the parent enables the substate.
+        world.reset_state::<SubState>(None);
+        world.run_schedule(StateUpdates);
+        assert_states!(
+            world,
+            (ManualState, ManualState::B),
+            (SubState, Some(SubState::X))
+        );
+    }
+
+    #[test]
+    fn update_override() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<ManualState>(StateConfig::empty());
+        world.register_state::<SubState>(StateConfig::empty());
+        let a = world.spawn_empty().id();
+        let b = world.spawn_empty().id();
+        for entity in [a, b] {
+            world.init_state(Some(entity), ManualState::A);
+            world.init_state(Some(entity), None::<SubState>);
+        }
+        world
+            .entity_mut(b)
+            .insert(StateUpdateOverride::<SubState>::new(|state, manual| {
+                let next = state.update_mut().take();
+                match manual.current() {
+                    ManualState::A => Some(next.unwrap_or(SubState::Y)),
+                    ManualState::B => None,
+                }
+            }));
+        world.update_state(Some(a), ManualState::A);
+        world.update_state(Some(b), ManualState::A);
+        world.run_schedule(StateUpdates);
+
+        let state = world.get::<StateData<SubState>>(a).unwrap();
+        assert_eq!(state.current(), &None);
+        let state = world.get::<StateData<SubState>>(b).unwrap();
+        assert_eq!(state.current(), &Some(SubState::Y));
+    }
+
+    #[test]
+    fn unregister_state() {
+        let world = &mut World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<StateTransitionTracker>();
+        world.register_state::<ManualState>(StateConfig::default());
+        let systems = world
+            .resource::<Schedules>()
+            .get(StateUpdates)
+            .unwrap()
+            .systems_len();
+        world.unregister_state::<ManualState>();
+        assert_eq!(
+            world
+                .resource::<Schedules>()
+                .get(StateUpdates)
+                .unwrap()
+                .systems_len(),
+            0
+        );
+        world.add_observer(track::<OnInit<ManualState>>());
+        world.add_observer(track::<OnEnter<ManualState>>());
+
+        // Machinery is gone, nothing gets triggered or updated.
+        world.init_state(None, ManualState::A);
+        world.update_state(None, ManualState::B);
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (ManualState, ManualState::A));
+        assert!(world.resource::<StateTransitionTracker>().0.is_empty());
+
+        // Registering again restores the machinery exactly once.
+        world.register_state::<ManualState>(StateConfig::default());
+        assert_eq!(
+            world
+                .resource::<Schedules>()
+                .get(StateUpdates)
+                .unwrap()
+                .systems_len(),
+            systems
+        );
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (ManualState, ManualState::B));
+        let transitions = &world.resource::<StateTransitionTracker>().0;
+        assert_eq!(transitions, &vec![type_name::<OnEnter<ManualState>>()]);
+    }
+
+    #[test]
+    fn reregister_deterministic_order() {
+        let world = &mut World::new();
+        world.init_resource::<Schedules>();
+        let config = StateConfig::default().with_deterministic_order(true);
+        world.register_state::<ManualState>(config.clone());
+        world.register_state::<ManualState2>(config.clone());
+        world.unregister_state::<ManualState>();
+        world.register_state::<ManualState>(config.with_priority(-1));
+        world.init_state(None, ManualState::A);
+        world.init_state(None, ManualState2::C);
+        world.run_schedule(StateUpdates);
+
+        // Tie break from the first registration is removed, new priority applies.
+        world.init_resource::<StateTransitionTracker>();
+        world.add_observer(track::<OnEnter<ManualState>>());
+        world.add_observer(track::<OnEnter<ManualState2>>());
+        world.update_state(None, ManualState::B);
+        world.update_state(None, ManualState2::D);
+        world.run_schedule(StateUpdates);
+        assert_eq!(
+            world.resource::<StateTransitionTracker>().0,
+            vec![
+                type_name::<OnEnter<ManualState2>>(),
+                type_name::<OnEnter<ManualState>>(),
+            ]
+        );
+    }
+
+    #[test]
+    fn state_errors() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<ManualState>(StateConfig::empty());
+        assert_eq!(
+            world.try_update_state(None, ManualState::B),
+            Err(StateError::NoGlobalEntity)
+        );
+
+        let entity = world.spawn_empty().id();
+        assert_eq!(
+            world.try_update_state(Some(entity), ManualState::B),
+            Err(StateError::missing_state::<ManualState>(entity))
+        );
+        assert_eq!(world.try_init_state(Some(entity), ManualState::A), Ok(()));
+        assert_eq!(
+            world.try_init_state(Some(entity), ManualState::A),
+            Err(StateError::already_initialized::<ManualState>(entity))
+        );
+        assert_eq!(world.try_update_state(Some(entity), ManualState::B), Ok(()));
+
+        // Despawned targets are reported instead of panicking.
+        world.despawn(entity);
+        assert_eq!(
+            world.try_update_state(Some(entity), ManualState::B),
+            Err(StateError::NoSuchEntity(entity))
+        );
+        assert_eq!(
+            world.try_init_state(Some(entity), ManualState::A),
+            Err(StateError::NoSuchEntity(entity))
+        );
+        world.reset_state::<ManualState>(Some(entity));
+        world.remove_state::<ManualState>(Some(entity));
+    }
+
+    #[test]
+    fn auto_init() {
+        let world = &mut World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<ManualState>(StateConfig::empty());
+        world.register_state::<ComputedState>(StateConfig::empty().with_auto_init(true));
+        world.register_state::<SubState>(StateConfig::empty().with_auto_init(true));
+
+        world.init_state(None, ManualState::B);
+        assert_states!(
+            world,
+            (ManualState, ManualState::B),
+            (ComputedState, None),
+            (SubState, Some(SubState::X)),
+        );
+
+        world.update_state(None, ManualState::A);
+        world.run_schedule(StateUpdates);
+        assert_states!(
+            world,
+            (ComputedState, Some(ComputedState)),
+            (SubState, None)
+        );
+    }
+
+    #[test]
+    fn auto_init_override() {
+        let world = &mut World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<ManualState>(StateConfig::empty());
+        world.register_state::<ComputedState>(StateConfig::empty().with_auto_init(true));
+        // Non-optional states can't be automatically initialized.
+        world.register_state::<IsA>(StateConfig::empty().with_auto_init(true));
+        let registration = world
+            .query_filtered::<&StateRegistration, With<RegisteredState<IsA>>>()
+            .single(world)
+            .unwrap();
+        assert_eq!(registration.config(), &StateConfig::empty());
+
+        let entity = world
+            .spawn(StateUpdateOverride::<ComputedState>::new(|_, _| {
+                Some(ComputedState)
+            }))
+            .id();
+        world.init_state(Some(entity), ManualState::B);
+        let entity = world.entity(entity);
+        assert_eq!(
+            entity.get::<StateData<ComputedState>>().unwrap().current(),
+            &Some(ComputedState)
+        );
+        assert!(!entity.contains::<StateData<IsA>>());
+    }
+
+    #[test]
+    fn initial_transitions() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<StateTransitionTracker>();
+        world.register_state::<ManualState>(StateConfig::default().with_initial_transitions(true));
+        world.add_observer(track::<OnExit<ManualState>>());
+        world.add_observer(track::<OnEnter<ManualState>>());
+        world.init_state(None, ManualState::A);
+        world.run_schedule(StateUpdates);
+        world.run_schedule(StateUpdates);
+
+        // Local state added at runtime.
+        let entity = world.spawn_empty().id();
+        world.init_state(Some(entity), ManualState::B);
+        world.run_schedule(StateUpdates);
+
+        let transitions = &world.resource::<StateTransitionTracker>().0;
+        assert_eq!(
+            transitions,
+            &vec![
+                type_name::<OnEnter<ManualState>>(),
+                type_name::<OnEnter<ManualState>>()
+            ]
+        );
+        let state = world
+            .entity(entity)
+            .get::<StateData<ManualState>>()
+            .unwrap();
+        assert_eq!(state.previous(), None);
+        assert!(state.is_updated());
+    }
+
+    #[test]
+    fn initial_transition_with_pending_update() {
+        let world = &mut World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<StateTransitionTracker>();
+        world.register_state::<ManualState>(StateConfig::default().with_initial_transitions(true));
+        world.add_observer(track::<OnExit<ManualState>>());
+        world.add_observer(track::<OnEnter<ManualState>>());
+        world.init_state(None, ManualState::A);
+        world.update_state(None, ManualState::B);
+
+        // Initial value is entered first.
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (ManualState, ManualState::A));
+        let state = world
+            .query::<&StateData<ManualState>>()
+            .single(world)
+            .unwrap();
+        assert_eq!(state.previous(), None);
+
+        // Pending update is applied afterwards.
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (ManualState, ManualState::B));
+        let transitions = &world.resource::<StateTransitionTracker>().0;
+        assert_eq!(
+            transitions,
+            &vec![
+                type_name::<OnEnter<ManualState>>(),
+                type_name::<OnExit<ManualState>>(),
+                type_name::<OnEnter<ManualState>>(),
+            ]
+        );
+    }
+
+    #[derive(Default, Resource)]
+    struct Edges(Vec<(Option<ManualState>, ManualState, bool)>);
+
+    #[test]
+    fn combined_transition() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<StateTransitionTracker>();
+        world.init_resource::<Edges>();
+        world.register_state::<ManualState>(StateConfig::default().with_on_transition(true));
+        world.add_observer(track::<OnExit<ManualState>>());
+        world.add_observer(track::<OnTransition<ManualState>>());
+        world.add_observer(track::<OnEnter<ManualState>>());
+        world.add_observer(
+            |trigger: On<OnTransition<ManualState>>, mut edges: ResMut<Edges>| {
+                edges
+                    .0
+                    .push((trigger.from.clone(), trigger.to.clone(), trigger.reentrant));
+            },
+        );
+        world.init_state(None, ManualState::A);
+
+        world.update_state(None, ManualState::B);
+        world.run_schedule(StateUpdates);
+        world.update_state(None, ManualState::B);
+        world.run_schedule(StateUpdates);
+
+        let transitions = &world.resource::<StateTransitionTracker>().0;
+        assert_eq!(transitions[0], type_name::<OnExit<ManualState>>());
+        assert_eq!(transitions[1], type_name::<OnTransition<ManualState>>());
+        assert_eq!(transitions[2], type_name::<OnEnter<ManualState>>());
+        assert_eq!(
+            world.resource::<Edges>().0,
+            vec![
+                (Some(ManualState::A), ManualState::B, false),
+                (Some(ManualState::B), ManualState::B, true),
+            ]
+        );
+    }
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Combat {
+        Peace,
+        Fight,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Inventory {
+        Closed,
+        Open,
+    }
+
+    impl State for Inventory {
+        type Dependencies = Combat;
+        type Update = Option<Self>;
+        type Repr = Self;
+
+        fn update(
+            state: &mut StateData<Self>,
+            _: StateSetData<'_, Self::Dependencies>,
+        ) -> Self::Repr {
+            state
+                .update_mut()
+                .take()
+                .unwrap_or_else(|| state.current().clone())
+        }
+
+        fn guard(
+            _state: &StateData<Self>,
+            next: &Self::Repr,
+            dependencies: StateSetData<'_, Self::Dependencies>,
+        ) -> bool {
+            let combat = dependencies;
+            !(next == &Inventory::Open && combat.current() == &Combat::Fight)
+        }
+    }
+
+    #[derive(Default, Resource)]
+    struct Rejections(Vec<(Inventory, Inventory)>);
+
+    #[test]
+    fn transition_guard() {
+        let world = &mut World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<Rejections>();
+        world.register_state::<Combat>(StateConfig::empty());
+        world.register_state::<Inventory>(StateConfig::empty());
+        world.add_observer(
+            |trigger: On<OnTransitionRejected<Inventory>>, mut rejections: ResMut<Rejections>| {
+                rejections
+                    .0
+                    .push((trigger.from.clone(), trigger.to.clone()));
+            },
+        );
+        world.init_state(None, Combat::Fight);
+        world.init_state(None, Inventory::Closed);
+
+        world.update_state(None, Inventory::Open);
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (Inventory, Inventory::Closed));
+        let inventory = world
+            .query::<&StateData<Inventory>>()
+            .single(world)
+            .unwrap();
+        assert!(!inventory.is_updated());
+        assert_eq!(
+            world.resource::<Rejections>().0,
+            vec![(Inventory::Closed, Inventory::Open)]
+        );
+
+        world.update_state(None, Combat::Peace);
+        world.update_state(None, Inventory::Open);
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (Inventory, Inventory::Open));
+        assert_eq!(world.resource::<Rejections>().0.len(), 1);
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Loot {
+        Open,
+    }
+
+    impl State for Loot {
+        type Dependencies = Combat;
+        type Update = Option<Self>;
+        type Repr = Option<Self>;
+
+        fn update(
+            state: &mut StateData<Self>,
+            dependencies: StateSetData<'_, Self::Dependencies>,
+        ) -> Self::Repr {
+            let combat = dependencies;
+            let next = state.update_mut().take();
+            match combat.current() {
+                Combat::Peace => next.or_else(|| state.current().clone()),
+                Combat::Fight => None,
+            }
+        }
+
+        fn guard(
+            _state: &StateData<Self>,
+            next: &Self::Repr,
+            _dependencies: StateSetData<'_, Self::Dependencies>,
+        ) -> bool {
+            // Loot can't be closed manually.
+            next.is_some()
+        }
+    }
+
+    #[test]
+    fn guard_ignores_dependency_updates() {
+        let world = &mut World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Combat>(StateConfig::empty());
+        world.register_state::<Loot>(StateConfig::empty());
+        world.init_state(None, Combat::Peace);
+        world.init_state(None, None::<Loot>);
+        world.update_state(None, Loot::Open);
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (Loot, Some(Loot::Open)));
+
+        // Parent forces the substate off, even though the guard would reject it.
+        world.update_state(None, Combat::Fight);
+        world.run_schedule(StateUpdates);
+        assert_states!(world, (Loot, None));
+    }
+
     // Debug stuff
 
     #[allow(unused_macros)]
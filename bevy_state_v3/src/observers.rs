@@ -0,0 +1,307 @@
+//! Transition observers filtered by state value.
+//!
+//! Instead of checking the transition value at the start of every observer:
+//! ```rs
+//! app.add_observer(|trigger: On<OnEnter<MyState>>| {
+//!     if trigger.0 != MyState::Foo {
+//!         return;
+//!     }
+//!     // ...
+//! });
+//! ```
+//! a system can be registered to only run for the matching value:
+//! ```rs
+//! app.add_state_observer(on_enter(MyState::Foo), || {
+//!     // ...
+//! });
+//! // Equivalent to the above.
+//! app.add_state_observer(OnEnter::<MyState>::when(MyState::Foo), || {
+//!     // ...
+//! });
+//! ```
+//! Systems can accept the target entity of local state transitions
+//! through [`In<Option<Entity>>`], which is [`None`] for global states.
+//!
+//! Matching systems don't run inside the observer, but through [`Commands`],
+//! so they run after all other observers of the same transition event.
+//! Systems and their observers are removed when the state is unregistered.
+
+use core::marker::PhantomData;
+
+use bevy_ecs::{
+    entity::Entity,
+    event::Event,
+    observer::On,
+    query::With,
+    system::{Commands, In, IntoSystem, SystemId, SystemInput},
+    world::World,
+};
+
+use crate::{
+    components::{RegisteredState, StateRegistration},
+    state::{State, StateRepr},
+    transitions::{OnEnter, OnExit, OnReenter, OnReexit},
+};
+
+/// Transition events which carry a single state value.
+pub trait TransitionEvent: Event + Sized {
+    /// State this event was triggered for.
+    type State: State;
+
+    /// Value of the state this transition refers to.
+    fn value(&self) -> &<Self::State as State>::Repr;
+
+    /// Creates a filter which matches this event with the provided value.
+    fn when(value: <Self::State as State>::Repr) -> TransitionFilter<Self> {
+        TransitionFilter {
+            value,
+            _event: PhantomData,
+        }
+    }
+}
+
+macro_rules! impl_transition_event {
+    ($($event:ident),*) => {
+        $(
+            impl<S: State> TransitionEvent for $event<S> {
+                type State = S;
+
+                fn value(&self) -> &S::Repr {
+                    &self.0
+                }
+            }
+        )*
+    };
+}
+
+impl_transition_event!(OnEnter, OnExit, OnReenter, OnReexit);
+
+/// Filter for transition events of a specific value.
+pub struct TransitionFilter<E: TransitionEvent> {
+    value: <E::State as State>::Repr,
+    _event: PhantomData<E>,
+}
+
+impl<E: TransitionEvent> TransitionFilter<E> {
+    /// Returns whether the event matches this filter.
+    pub fn matches(&self, event: &E) -> bool {
+        event.value() == &self.value
+    }
+}
+
+/// Filter for [`OnEnter`] of the specified value.
+pub fn on_enter<R: StateRepr>(value: R) -> TransitionFilter<OnEnter<R::State>> {
+    OnEnter::when(value)
+}
+
+/// Filter for [`OnExit`] of the specified value.
+pub fn on_exit<R: StateRepr>(value: R) -> TransitionFilter<OnExit<R::State>> {
+    OnExit::when(value)
+}
+
+/// Filter for [`OnReenter`] of the specified value.
+pub fn on_reenter<R: StateRepr>(value: R) -> TransitionFilter<OnReenter<R::State>> {
+    OnReenter::when(value)
+}
+
+/// Filter for [`OnReexit`] of the specified value.
+pub fn on_reexit<R: StateRepr>(value: R) -> TransitionFilter<OnReexit<R::State>> {
+    OnReexit::when(value)
+}
+
+/// Inputs of systems which can be used with filtered transition observers.
+/// This can be either:
+/// - [`()`] - no input,
+/// - [`In<Option<Entity>>`] - target entity of the transition, [`None`] for global states.
+pub trait TransitionInput: SystemInput<Inner<'static>: Send> + 'static {
+    /// Creates the system input from transition target.
+    fn from_target(target: Option<Entity>) -> Self::Inner<'static>;
+}
+
+impl TransitionInput for () {
+    fn from_target(_target: Option<Entity>) {}
+}
+
+impl TransitionInput for In<Option<Entity>> {
+    fn from_target(target: Option<Entity>) -> Option<Entity> {
+        target
+    }
+}
+
+/// Creates an observer which runs the system if the event matches the filter.
+fn filtered_observer<E: TransitionEvent, I: TransitionInput>(
+    filter: TransitionFilter<E>,
+    system: SystemId<I>,
+) -> impl Fn(On<E>, Commands) {
+    move |trigger: On<E>, mut commands: Commands| {
+        if filter.matches(&trigger) {
+            commands.run_system_with(system, I::from_target(trigger.target()));
+        }
+    }
+}
+
+/// Adds an observer which runs the registered system if the event matches the filter.
+/// Both are tracked by the registration of the state, so they are removed when the state is unregistered.
+fn add_filtered_observer<E: TransitionEvent, I: TransitionInput>(
+    world: &mut World,
+    filter: TransitionFilter<E>,
+    system: SystemId<I>,
+) {
+    let observer = world.add_observer(filtered_observer(filter, system)).id();
+    if let Ok(mut registration) = world
+        .query_filtered::<&mut StateRegistration, With<RegisteredState<E::State>>>()
+        .single_mut(world)
+    {
+        registration.observers.extend([observer, system.entity()]);
+    }
+}
+
+/// Methods for registering filtered transition observers.
+///
+/// Depending on which medium this is called on, those methods will have:
+/// - immediate effect: [`World`], [`SubApp`](bevy_app::SubApp) and [`App`](bevy_app::App),
+/// - deferred effect: [`Commands`].
+pub trait StateObserversExt {
+    /// Registers a system which runs whenever the transition event matches the filter.
+    /// Works for both global and local states.
+    /// The state should be registered first, so the system is removed when the state is unregistered.
+    /// The system runs through [`Commands`], after other observers of the event.
+    fn add_state_observer<E: TransitionEvent, I: TransitionInput, M>(
+        &mut self,
+        filter: TransitionFilter<E>,
+        system: impl IntoSystem<I, (), M> + Send + 'static,
+    ) -> &mut Self;
+}
+
+impl StateObserversExt for Commands<'_, '_> {
+    fn add_state_observer<E: TransitionEvent, I: TransitionInput, M>(
+        &mut self,
+        filter: TransitionFilter<E>,
+        system: impl IntoSystem<I, (), M> + Send + 'static,
+    ) -> &mut Self {
+        let system = self.register_system(system);
+        self.queue(move |world: &mut World| {
+            add_filtered_observer(world, filter, system);
+        });
+        self
+    }
+}
+
+impl StateObserversExt for World {
+    fn add_state_observer<E: TransitionEvent, I: TransitionInput, M>(
+        &mut self,
+        filter: TransitionFilter<E>,
+        system: impl IntoSystem<I, (), M> + Send + 'static,
+    ) -> &mut Self {
+        let system = self.register_system(system);
+        add_filtered_observer(self, filter, system);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl StateObserversExt for bevy_app::SubApp {
+    fn add_state_observer<E: TransitionEvent, I: TransitionInput, M>(
+        &mut self,
+        filter: TransitionFilter<E>,
+        system: impl IntoSystem<I, (), M> + Send + 'static,
+    ) -> &mut Self {
+        self.world_mut().add_state_observer(filter, system);
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl StateObserversExt for bevy_app::App {
+    fn add_state_observer<E: TransitionEvent, I: TransitionInput, M>(
+        &mut self,
+        filter: TransitionFilter<E>,
+        system: impl IntoSystem<I, (), M> + Send + 'static,
+    ) -> &mut Self {
+        self.main_mut().add_state_observer(filter, system);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        entity::Entity,
+        resource::Resource,
+        schedule::Schedules,
+        system::{In, ResMut},
+        world::World,
+    };
+    use bevy_state_macros::State;
+
+    use crate::{
+        self as bevy_state_v3,
+        commands::CoreStatesExt,
+        config::StateConfig,
+        observers::{StateObserversExt, TransitionEvent, on_enter, on_exit},
+        system_set::StateUpdates,
+        transitions::OnEnter,
+    };
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Door {
+        Open,
+        Closed,
+    }
+
+    #[derive(Default, Resource)]
+    struct Calls(Vec<(&'static str, Option<Entity>)>);
+
+    #[test]
+    fn filtered_observers() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<Calls>();
+        world.register_state::<Door>(StateConfig::default());
+        world.add_state_observer(on_enter(Door::Open), |mut calls: ResMut<Calls>| {
+            calls.0.push(("enter open", None));
+        });
+        world.add_state_observer(
+            OnEnter::<Door>::when(Door::Closed),
+            |In(target): In<Option<Entity>>, mut calls: ResMut<Calls>| {
+                calls.0.push(("enter closed", target));
+            },
+        );
+        world.add_state_observer(on_exit(Door::Open), |mut calls: ResMut<Calls>| {
+            calls.0.push(("exit open", None));
+        });
+        let local = world.spawn_empty().id();
+        world.init_state(None, Door::Closed);
+        world.init_state(Some(local), Door::Open);
+
+        world.update_state(None, Door::Open);
+        world.run_schedule(StateUpdates);
+        assert_eq!(world.resource::<Calls>().0, vec![("enter open", None)]);
+
+        world.resource_mut::<Calls>().0.clear();
+        world.update_state(Some(local), Door::Closed);
+        world.run_schedule(StateUpdates);
+        assert_eq!(
+            world.resource::<Calls>().0,
+            vec![("exit open", None), ("enter closed", Some(local))]
+        );
+    }
+
+    #[test]
+    fn unregistered_observers() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<Calls>();
+        world.register_state::<Door>(StateConfig::default());
+        world.add_state_observer(on_enter(Door::Open), |mut calls: ResMut<Calls>| {
+            calls.0.push(("enter open", None));
+        });
+        world.unregister_state::<Door>();
+        world.register_state::<Door>(StateConfig::default());
+        world.init_state(None, Door::Closed);
+
+        world.update_state(None, Door::Open);
+        world.run_schedule(StateUpdates);
+        assert!(world.resource::<Calls>().0.is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+//! Queries over local states.
+
+use bevy_ecs::{
+    entity::Entity,
+    query::{QueryData, QueryFilter, QueryItem, ROQueryItem},
+    system::{Query, SystemParam},
+};
+
+use crate::{components::StateData, state::State};
+
+/// Query for entities with local state `S`.
+/// Replaces manual state matching with iterators that only yield entities in the requested state.
+/// ```rs
+/// fn move_enabled(mut logos: StateQuery<LogoState, &mut Transform>) {
+///     for (entity, mut transform) in logos.iter_in_mut(LogoState::Enabled) {
+///         // ...
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct StateQuery<'w, 's, S: State, D: QueryData + 'static = (), F: QueryFilter + 'static = ()>
+{
+    query: Query<'w, 's, (Entity, &'static StateData<S>, D), F>,
+}
+
+impl<'w, 's, S: State, D: QueryData + 'static, F: QueryFilter + 'static>
+    StateQuery<'w, 's, S, D, F>
+{
+    /// Returns the underlying query.
+    pub fn inner(&self) -> &Query<'w, 's, (Entity, &'static StateData<S>, D), F> {
+        &self.query
+    }
+
+    /// Returns the underlying query mutably.
+    pub fn inner_mut(&mut self) -> &mut Query<'w, 's, (Entity, &'static StateData<S>, D), F> {
+        &mut self.query
+    }
+
+    /// Iterates over entities which are in the target state.
+    pub fn iter_in(
+        &self,
+        target: S::Repr,
+    ) -> impl Iterator<Item = (Entity, ROQueryItem<'_, D>)> + '_ {
+        self.query
+            .iter()
+            .filter(move |(_, state, _)| state.current() == &target)
+            .map(|(entity, _, data)| (entity, data))
+    }
+
+    /// Iterates mutably over entities which are in the target state.
+    pub fn iter_in_mut(
+        &mut self,
+        target: S::Repr,
+    ) -> impl Iterator<Item = (Entity, QueryItem<'_, D>)> + '_ {
+        self.query
+            .iter_mut()
+            .filter(move |(_, state, _)| state.current() == &target)
+            .map(|(entity, _, data)| (entity, data))
+    }
+
+    /// Iterates over entities which state was updated during last state transition.
+    /// Reentrant transitions are included.
+    pub fn iter_updated(&self) -> impl Iterator<Item = (Entity, ROQueryItem<'_, D>)> + '_ {
+        self.query
+            .iter()
+            .filter(|(_, state, _)| state.is_updated())
+            .map(|(entity, _, data)| (entity, data))
+    }
+
+    /// Iterates mutably over entities which state was updated during last state transition.
+    /// Reentrant transitions are included.
+    pub fn iter_updated_mut(&mut self) -> impl Iterator<Item = (Entity, QueryItem<'_, D>)> + '_ {
+        self.query
+            .iter_mut()
+            .filter(|(_, state, _)| state.is_updated())
+            .map(|(entity, _, data)| (entity, data))
+    }
+
+    /// Iterates over entities which entered the target state during last state transition.
+    /// Reentrant transitions are ignored.
+    pub fn iter_entered(
+        &self,
+        target: S::Repr,
+    ) -> impl Iterator<Item = (Entity, ROQueryItem<'_, D>)> + '_ {
+        self.query
+            .iter()
+            .filter(move |(_, state, _)| is_entered(state, &target))
+            .map(|(entity, _, data)| (entity, data))
+    }
+
+    /// Iterates mutably over entities which entered the target state during last state transition.
+    /// Reentrant transitions are ignored.
+    pub fn iter_entered_mut(
+        &mut self,
+        target: S::Repr,
+    ) -> impl Iterator<Item = (Entity, QueryItem<'_, D>)> + '_ {
+        self.query
+            .iter_mut()
+            .filter(move |(_, state, _)| is_entered(state, &target))
+            .map(|(entity, _, data)| (entity, data))
+    }
+}
+
+fn is_entered<S: State>(state: &StateData<S>, target: &S::Repr) -> bool {
+    state.is_updated() && !state.is_reentrant() && state.current() == target
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        component::Component, entity::Entity, schedule::Schedules, system::RunSystemOnce,
+        world::World,
+    };
+    use bevy_state_macros::State;
+
+    use crate::{
+        self as bevy_state_v3, commands::CoreStatesExt, config::StateConfig, query::StateQuery,
+        system_set::StateUpdates,
+    };
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Logo {
+        Enabled,
+        Disabled,
+    }
+
+    #[derive(Component)]
+    struct Speed(u32);
+
+    #[test]
+    fn local_state_query() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Logo>(StateConfig::empty());
+        let a = world.spawn(Speed(1)).id();
+        let b = world.spawn(Speed(2)).id();
+        world.init_state(Some(a), Logo::Enabled);
+        world.init_state(Some(b), Logo::Enabled);
+        world.update_state(Some(b), Logo::Disabled);
+        world.run_schedule(StateUpdates);
+
+        let enabled = world
+            .run_system_once(|query: StateQuery<Logo, &Speed>| {
+                query
+                    .iter_in(Logo::Enabled)
+                    .map(|(entity, speed)| (entity, speed.0))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap();
+        assert_eq!(enabled, vec![(a, 1)]);
+
+        let entered = world
+            .run_system_once(|mut query: StateQuery<Logo, &mut Speed>| {
+                query
+                    .iter_entered_mut(Logo::Disabled)
+                    .map(|(entity, mut speed)| {
+                        speed.0 = 0;
+                        entity
+                    })
+                    .collect::<Vec<Entity>>()
+            })
+            .unwrap();
+        assert_eq!(entered, vec![b]);
+        assert_eq!(world.get::<Speed>(b).unwrap().0, 0);
+    }
+}
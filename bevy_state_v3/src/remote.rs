@@ -0,0 +1,359 @@
+//! Dependencies on states of other entities.
+//!
+//! Dependencies of a state have to be stored on the same entity.
+//! To depend on a state of another entity, a proxy state can be used instead,
+//! which is stored on the same entity and synchronized with the remote state during updates:
+//! - [`GlobalState`] - mirrors the global state,
+//! - [`RelatedState`] - mirrors the state of an entity targeted by a relationship, like [`ChildOf`](bevy_ecs::hierarchy::ChildOf).
+//!
+//! ```rs
+//! impl State for Behavior {
+//!     type Dependencies = (GlobalState<GameState>, RelatedState<ChildOf, LeaderState>);
+//!     type Update = Option<Self>;
+//!     type Repr = Option<Self>;
+//!
+//!     fn update(
+//!         state: &mut StateData<Self>,
+//!         dependencies: StateSetData<'_, Self::Dependencies>,
+//!     ) -> Self::Repr {
+//!         let (game, leader) = dependencies;
+//!         match (&game.current().0, &leader.current().0) {
+//!             (GameState::Playing, LeaderState::Charge) => Some(Behavior::Attack),
+//!             (GameState::Playing, _) => Some(Behavior::Follow),
+//!             _ => None,
+//!         }
+//!     }
+//! }
+//!
+//! app.register_state::<GlobalState<GameState>>(StateConfig::empty())
+//!     .register_state::<RelatedState<ChildOf, LeaderState>>(StateConfig::empty())
+//!     .register_state::<Behavior>(StateConfig::default());
+//! ```
+//! Proxy states have to be initialized on the entity like any other state,
+//! the initial value is overwritten with the remote value during the next update.
+//! They can't be initialized by [`StateConfig::with_auto_init`](crate::config::StateConfig::with_auto_init),
+//! because they are not optional and have no dependencies on their own entity,
+//! but dependent states with auto initialization are initialized once the proxies are.
+//! Resetting a proxy state synchronizes it with the remote value again.
+//!
+//! Remote states are recorded as remote dependencies of the proxies,
+//! so registering a proxy before the remote state is reported and the [`StateGraph`](crate::graph::StateGraph) shows the edge.
+//!
+//! Proxy states update right after the remote state, so changes propagate within the same update.
+//! A state can't depend on a proxy of itself.
+
+use core::{
+    fmt::{Debug, Formatter},
+    marker::PhantomData,
+};
+
+use bevy_ecs::{
+    component::ComponentId,
+    entity::Entity,
+    query::With,
+    relationship::Relationship,
+    schedule::{IntoScheduleConfigs, Schedule},
+    system::Query,
+    world::World,
+};
+
+use crate::{
+    components::{StateData, StateRegistration},
+    state::State,
+    state_set::StateSetData,
+    system_set::StateSystemSet,
+    util::GlobalMarker,
+};
+
+/// Proxy of the global state `S`, which can be used as a dependency of local states.
+pub struct GlobalState<S: State>(pub S::Repr);
+
+impl<S: State> Clone for GlobalState<S> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<S: State> Debug for GlobalState<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("GlobalState").field(&self.0).finish()
+    }
+}
+
+impl<S: State> PartialEq for GlobalState<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<S: State> State for GlobalState<S> {
+    type Dependencies = ();
+    type Update = Option<Self>;
+    type Repr = Self;
+
+    const ORDER: u32 = S::ORDER + 1;
+
+    fn update(state: &mut StateData<Self>, _: StateSetData<'_, Self::Dependencies>) -> Self::Repr {
+        take_remote_value(state)
+    }
+
+    fn remote_dependencies(world: &mut World) -> Vec<(ComponentId, &'static str)> {
+        remote_dependency::<S>(world)
+    }
+
+    fn register_systems(schedule: &mut Schedule, registration: Entity) {
+        schedule.add_systems(
+            sync_global_state::<S>
+                .in_set(StateSystemSet::update::<Self>())
+                .before(Self::update_state_data_system)
+                .run_if(StateRegistration::is_active(registration)),
+        );
+    }
+}
+
+/// Proxy of state `S` stored on the entity targeted by relationship `R`,
+/// which can be used as a dependency of local states.
+///
+/// If the relationship or the remote state is missing, the proxy keeps its last value.
+pub struct RelatedState<R: Relationship, S: State>(pub S::Repr, PhantomData<fn() -> R>);
+
+impl<R: Relationship, S: State> RelatedState<R, S> {
+    /// Creates a new proxy value.
+    pub fn new(value: S::Repr) -> Self {
+        Self(value, PhantomData)
+    }
+}
+
+impl<R: Relationship, S: State> Clone for RelatedState<R, S> {
+    fn clone(&self) -> Self {
+        Self::new(self.0.clone())
+    }
+}
+
+impl<R: Relationship, S: State> Debug for RelatedState<R, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("RelatedState").field(&self.0).finish()
+    }
+}
+
+impl<R: Relationship, S: State> PartialEq for RelatedState<R, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<R: Relationship, S: State> State for RelatedState<R, S> {
+    type Dependencies = ();
+    type Update = Option<Self>;
+    type Repr = Self;
+
+    const ORDER: u32 = S::ORDER + 1;
+
+    fn update(state: &mut StateData<Self>, _: StateSetData<'_, Self::Dependencies>) -> Self::Repr {
+        take_remote_value(state)
+    }
+
+    fn remote_dependencies(world: &mut World) -> Vec<(ComponentId, &'static str)> {
+        remote_dependency::<S>(world)
+    }
+
+    fn register_systems(schedule: &mut Schedule, registration: Entity) {
+        schedule.add_systems(
+            sync_related_state::<R, S>
+                .in_set(StateSystemSet::update::<Self>())
+                .before(Self::update_state_data_system)
+                .run_if(StateRegistration::is_active(registration)),
+        );
+    }
+}
+
+/// Remote state `S` as the only remote dependency of a proxy.
+fn remote_dependency<S: State>(world: &mut World) -> Vec<(ComponentId, &'static str)> {
+    vec![(
+        world.register_component::<StateData<S>>(),
+        core::any::type_name::<S>(),
+    )]
+}
+
+/// Proxy value is synchronized through the update, otherwise it stays the same.
+fn take_remote_value<S: State<Repr = S, Update = Option<S>>>(state: &mut StateData<S>) -> S {
+    state
+        .update_mut()
+        .take()
+        .unwrap_or_else(|| state.current().clone())
+}
+
+/// System which requests updates of [`GlobalState`] proxies,
+/// if the global state was updated, the value differs or the proxy is being reset.
+pub fn sync_global_state<S: State>(
+    global: Query<&StateData<S>, With<GlobalMarker>>,
+    mut proxies: Query<&mut StateData<GlobalState<S>>>,
+) {
+    let Ok(global) = global.single() else {
+        return;
+    };
+    for mut proxy in proxies.iter_mut() {
+        if global.is_updated() || proxy.reset || proxy.current().0 != *global.current() {
+            *proxy.update_mut() = Some(GlobalState(global.current().clone()));
+        }
+    }
+}
+
+/// System which requests updates of [`RelatedState`] proxies,
+/// if the related state was updated, the value differs or the proxy is being reset.
+pub fn sync_related_state<R: Relationship, S: State>(
+    remotes: Query<&StateData<S>>,
+    mut proxies: Query<(&mut StateData<RelatedState<R, S>>, &R)>,
+) {
+    for (mut proxy, relationship) in proxies.iter_mut() {
+        let Ok(remote) = remotes.get(relationship.get()) else {
+            continue;
+        };
+        if remote.is_updated() || proxy.reset || proxy.current().0 != *remote.current() {
+            *proxy.update_mut() = Some(RelatedState::new(remote.current().clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::any::type_name;
+
+    use bevy_ecs::{
+        hierarchy::ChildOf,
+        schedule::Schedules,
+        world::{FromWorld, World},
+    };
+    use bevy_state_macros::State;
+
+    use crate::{
+        self as bevy_state_v3,
+        commands::CoreStatesExt,
+        components::StateData,
+        config::StateConfig,
+        error::StateError,
+        graph::StateGraph,
+        remote::{GlobalState, RelatedState},
+        state::State,
+        state_set::StateSetData,
+        system_set::StateUpdates,
+        validation::validate_registration,
+    };
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Game {
+        Menu,
+        Playing,
+    }
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Leader {
+        Hold,
+        Charge,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Unit {
+        Follow,
+        Attack,
+    }
+
+    impl State for Unit {
+        type Dependencies = (GlobalState<Game>, RelatedState<ChildOf, Leader>);
+        type Update = ();
+        type Repr = Option<Self>;
+
+        fn update(
+            _state: &mut StateData<Self>,
+            dependencies: StateSetData<'_, Self::Dependencies>,
+        ) -> Self::Repr {
+            let (game, leader) = dependencies;
+            match (&game.current().0, &leader.current().0) {
+                (Game::Playing, Leader::Charge) => Some(Unit::Attack),
+                (Game::Playing, _) => Some(Unit::Follow),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn remote_dependencies() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Game>(StateConfig::empty());
+        world.register_state::<Leader>(StateConfig::empty());
+        world.register_state::<GlobalState<Game>>(StateConfig::empty());
+        world.register_state::<RelatedState<ChildOf, Leader>>(StateConfig::empty());
+        world.register_state::<Unit>(StateConfig::empty());
+        let leader = world.spawn_empty().id();
+        let unit = world.spawn(ChildOf(leader)).id();
+        world.init_state(None, Game::Menu);
+        world.init_state(Some(leader), Leader::Hold);
+        world.init_state(Some(unit), GlobalState::<Game>(Game::Menu));
+        world.init_state(
+            Some(unit),
+            RelatedState::<ChildOf, Leader>::new(Leader::Hold),
+        );
+        world.init_state(Some(unit), None::<Unit>);
+
+        world.update_state(None, Game::Playing);
+        world.run_schedule(StateUpdates);
+        let state = world.get::<StateData<Unit>>(unit).unwrap();
+        assert_eq!(state.current(), &Some(Unit::Follow));
+
+        world.update_state(Some(leader), Leader::Charge);
+        world.run_schedule(StateUpdates);
+        let state = world.get::<StateData<Unit>>(unit).unwrap();
+        assert_eq!(state.current(), &Some(Unit::Attack));
+        let proxy = world
+            .get::<StateData<RelatedState<ChildOf, Leader>>>(unit)
+            .unwrap();
+        assert_eq!(proxy.current().0, Leader::Charge);
+    }
+
+    #[test]
+    fn remote_dependency_registration() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        assert_eq!(
+            validate_registration::<RelatedState<ChildOf, Leader>>(&mut world),
+            Err(StateError::UnregisteredDependencies {
+                state: type_name::<RelatedState<ChildOf, Leader>>(),
+                missing: vec![type_name::<Leader>()],
+            })
+        );
+
+        world.register_state::<Game>(StateConfig::empty());
+        world.register_state::<GlobalState<Game>>(StateConfig::empty());
+        let graph = StateGraph::from_world(&mut world);
+        let proxy = graph.node::<GlobalState<Game>>().unwrap();
+        assert!(proxy.dependencies.is_empty());
+        assert_eq!(proxy.remote_dependencies, vec![type_name::<Game>()]);
+        let dependents = graph.dependents::<Game>().map(|node| node.type_name);
+        assert_eq!(
+            dependents.collect::<Vec<_>>(),
+            vec![type_name::<GlobalState<Game>>()]
+        );
+    }
+
+    #[test]
+    fn reset_proxy() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Game>(StateConfig::empty());
+        world.register_state::<GlobalState<Game>>(StateConfig::empty());
+        let unit = world.spawn_empty().id();
+        world.init_state(None, Game::Menu);
+        world.init_state(Some(unit), GlobalState::<Game>(Game::Menu));
+
+        world.update_state(None, Game::Playing);
+        world.run_schedule(StateUpdates);
+        world.run_schedule(StateUpdates);
+
+        // Proxy resets to the remote value, not to the value it was initialized with.
+        world.reset_state::<GlobalState<Game>>(Some(unit));
+        world.run_schedule(StateUpdates);
+        let proxy = world.get::<StateData<GlobalState<Game>>>(unit).unwrap();
+        assert_eq!(proxy.current().0, Game::Playing);
+    }
+}
@@ -0,0 +1,353 @@
+//! Schedule based transitions, compatible with the upstream `bevy_state` crate.
+//!
+//! Those schedules exist to ease the migration from `bevy_state`,
+//! observable transition events from [`transitions`](crate::transitions) should be preferred.
+//! ```rs
+//! app.register_state::<GameState>(StateConfig::default())
+//!     .register_state_schedules::<GameState>()
+//!     .add_systems(EnterSchedule(GameState::Menu), spawn_menu)
+//!     .add_systems(ExitSchedule(GameState::Menu), despawn_menu)
+//!     .init_state(None, GameState::Menu);
+//! ```
+//! Schedule labels are named differently than upstream `OnEnter`, `OnExit` and `OnTransition`,
+//! so they don't collide with transition events from [`transitions`](crate::transitions).
+//!
+//! Schedules only run for global states.
+//! Similarly to the upstream crate, [`EnterSchedule`] also runs for the initial state value,
+//! which happens immediately during state initialization, or during the next state update
+//! if the state is registered with [`StateConfig::with_initial_transitions`].
+
+use core::hash::Hash;
+
+use bevy_ecs::{
+    entity::Entity,
+    lifecycle::Add,
+    observer::On,
+    prelude::{Commands, World},
+    query::{Has, QueryState, With},
+    schedule::{IntoScheduleConfigs, Schedule, ScheduleLabel, Schedules},
+    system::Query,
+};
+
+use bevy_log::warn;
+
+use crate::{
+    components::{RegisteredState, StateData, StateRegistration},
+    config::StateConfig,
+    state::{State, StateRepr},
+    system_set::{StateSystemSet, StateUpdates},
+    util::GlobalMarker,
+};
+
+/// Schedule which runs when global state enters the value.
+/// Reentrant transitions are ignored.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EnterSchedule<R: StateRepr + Eq + Hash>(pub R);
+
+/// Schedule which runs when global state exits the value.
+/// Reentrant transitions are ignored.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExitSchedule<R: StateRepr + Eq + Hash>(pub R);
+
+/// Schedule which runs when global state changes between the values.
+/// Like upstream, runs after [`ExitSchedule`]s of all states and before any [`EnterSchedule`].
+/// Reentrant transitions are ignored.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TransitionSchedule<R: StateRepr + Eq + Hash> {
+    /// Value that was exited.
+    pub exited: R,
+    /// Value that was entered.
+    pub entered: R,
+}
+
+/// System which runs [`ExitSchedule`] schedules of global state.
+pub fn run_exit_schedules<S: State>(
+    world: &mut World,
+    query: &mut QueryState<&StateData<S>, With<GlobalMarker>>,
+) where
+    S::Repr: Eq + Hash,
+{
+    let Ok(state) = query.single(world) else {
+        return;
+    };
+    if !state.is_updated() || state.is_reentrant() {
+        return;
+    }
+    let Some(exited) = state.previous().cloned() else {
+        return;
+    };
+    let _ = world.try_run_schedule(ExitSchedule(exited));
+}
+
+/// System which runs [`TransitionSchedule`] schedules of global state.
+pub fn run_transition_schedules<S: State>(
+    world: &mut World,
+    query: &mut QueryState<&StateData<S>, With<GlobalMarker>>,
+) where
+    S::Repr: Eq + Hash,
+{
+    let Ok(state) = query.single(world) else {
+        return;
+    };
+    if !state.is_updated() || state.is_reentrant() {
+        return;
+    }
+    let Some(exited) = state.previous().cloned() else {
+        return;
+    };
+    let entered = state.current().clone();
+    let _ = world.try_run_schedule(TransitionSchedule { exited, entered });
+}
+
+/// System which runs [`EnterSchedule`] schedules of global state.
+pub fn run_enter_schedules<S: State>(
+    world: &mut World,
+    query: &mut QueryState<&StateData<S>, With<GlobalMarker>>,
+) where
+    S::Repr: Eq + Hash,
+{
+    let Ok(state) = query.single(world) else {
+        return;
+    };
+    if !state.is_updated() || state.is_reentrant() {
+        return;
+    }
+    let entered = state.current().clone();
+    let _ = world.try_run_schedule(EnterSchedule(entered));
+}
+
+/// Observer which runs the [`EnterSchedule`] for the initial global state value.
+pub fn run_initial_enter_schedule<S: State>(
+    trigger: On<Add, StateData<S>>,
+    mut commands: Commands,
+    query: Query<(&StateData<S>, Has<GlobalMarker>)>,
+) where
+    S::Repr: Eq + Hash,
+{
+    let entity = trigger.target().unwrap();
+    let Ok((state, true)) = query.get(entity) else {
+        return;
+    };
+    let entered = state.current().clone();
+    commands.queue(move |world: &mut World| {
+        // States with initial transitions enter the initial value during the next state update.
+        if world
+            .get::<StateData<S>>(entity)
+            .is_none_or(|state| state.initial_transition)
+        {
+            return;
+        }
+        let _ = world.try_run_schedule(EnterSchedule(entered));
+    });
+}
+
+/// Methods for registering schedule based transitions.
+///
+/// Depending on which medium this is called on, those methods will have:
+/// - immediate effect: [`World`], [`SubApp`](bevy_app::SubApp) and [`App`](bevy_app::App),
+/// - deferred effect: [`Commands`].
+pub trait StateSchedulesExt {
+    /// Registers [`EnterSchedule`], [`ExitSchedule`] and [`TransitionSchedule`] schedules for the global state.
+    /// The state has to be registered first and schedules should only be registered once.
+    /// Schedules are unregistered together with the state.
+    fn register_state_schedules<S: State>(&mut self) -> &mut Self
+    where
+        S::Repr: Eq + Hash;
+}
+
+impl StateSchedulesExt for Commands<'_, '_> {
+    fn register_state_schedules<S: State>(&mut self) -> &mut Self
+    where
+        S::Repr: Eq + Hash,
+    {
+        self.queue(|world: &mut World| {
+            world.register_state_schedules::<S>();
+        });
+        self
+    }
+}
+
+impl StateSchedulesExt for World {
+    fn register_state_schedules<S: State>(&mut self) -> &mut Self
+    where
+        S::Repr: Eq + Hash,
+    {
+        let Ok((registration, state_registration)) = self
+            .query_filtered::<(Entity, &StateRegistration), With<RegisteredState<S>>>()
+            .single(self)
+        else {
+            warn!(
+                "Registering schedules failed, state {} is not registered.",
+                disqualified::ShortName::of::<S>()
+            );
+            return self;
+        };
+        let config = state_registration.config().clone();
+        let mut schedules = self.resource_mut::<Schedules>();
+        add_schedule_systems::<S>(schedules.entry(StateUpdates), registration, &config);
+        let observer = self.add_observer(run_initial_enter_schedule::<S>).id();
+        let mut registration = self.get_mut::<StateRegistration>(registration).unwrap();
+        registration.observers.push(observer);
+        registration.systems.push(add_schedule_systems::<S>);
+        self
+    }
+}
+
+/// Adds systems which run schedules of state `S` to the [`StateUpdates`] schedule.
+fn add_schedule_systems<S: State>(schedule: &mut Schedule, registration: Entity, _: &StateConfig)
+where
+    S::Repr: Eq + Hash,
+{
+    schedule.add_systems(
+        (
+            run_exit_schedules::<S>.in_set(StateSystemSet::exit::<S>()),
+            run_transition_schedules::<S>.in_set(StateSystemSet::transition::<S>()),
+            run_enter_schedules::<S>.in_set(StateSystemSet::enter::<S>()),
+        )
+            .run_if(StateRegistration::is_active(registration)),
+    );
+}
+
+#[cfg(feature = "bevy_app")]
+impl StateSchedulesExt for bevy_app::SubApp {
+    fn register_state_schedules<S: State>(&mut self) -> &mut Self
+    where
+        S::Repr: Eq + Hash,
+    {
+        self.world_mut().register_state_schedules::<S>();
+        self
+    }
+}
+
+#[cfg(feature = "bevy_app")]
+impl StateSchedulesExt for bevy_app::App {
+    fn register_state_schedules<S: State>(&mut self) -> &mut Self
+    where
+        S::Repr: Eq + Hash,
+    {
+        self.main_mut().register_state_schedules::<S>();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        resource::Resource,
+        schedule::{Schedule, Schedules},
+        system::ResMut,
+        world::World,
+    };
+    use bevy_state_macros::State;
+
+    use crate::{
+        self as bevy_state_v3,
+        commands::CoreStatesExt,
+        config::StateConfig,
+        schedules::{EnterSchedule, ExitSchedule, StateSchedulesExt, TransitionSchedule},
+        system_set::StateUpdates,
+    };
+
+    #[derive(State, Clone, Debug, PartialEq, Eq, Hash)]
+    enum GameState {
+        Menu,
+        InGame,
+    }
+
+    #[derive(State, Default, Clone, Debug, PartialEq, Eq, Hash)]
+    #[dependency(GameState = GameState::InGame)]
+    enum Pause {
+        #[default]
+        Running,
+    }
+
+    #[derive(Default, Resource)]
+    struct Log(Vec<&'static str>);
+
+    fn log(message: &'static str) -> impl Fn(ResMut<Log>) {
+        move |mut log: ResMut<Log>| log.0.push(message)
+    }
+
+    #[test]
+    fn compatibility_schedules() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<Log>();
+        world.register_state::<GameState>(StateConfig::empty());
+        world.register_state_schedules::<GameState>();
+        let mut schedules = world.resource_mut::<Schedules>();
+        let mut schedule = Schedule::new(EnterSchedule(GameState::Menu));
+        schedule.add_systems(log("enter menu"));
+        schedules.insert(schedule);
+        let mut schedule = Schedule::new(ExitSchedule(GameState::Menu));
+        schedule.add_systems(log("exit menu"));
+        schedules.insert(schedule);
+        let mut schedule = Schedule::new(TransitionSchedule {
+            exited: GameState::Menu,
+            entered: GameState::InGame,
+        });
+        schedule.add_systems(log("menu to game"));
+        schedules.insert(schedule);
+        let mut schedule = Schedule::new(EnterSchedule(GameState::InGame));
+        schedule.add_systems(log("enter game"));
+        schedules.insert(schedule);
+
+        world.init_state(None, GameState::Menu);
+        assert_eq!(world.resource::<Log>().0, vec!["enter menu"]);
+
+        world.update_state(None, GameState::InGame);
+        world.run_schedule(StateUpdates);
+        assert_eq!(
+            world.resource::<Log>().0,
+            vec!["enter menu", "exit menu", "menu to game", "enter game"]
+        );
+    }
+
+    #[test]
+    fn initial_transition_schedules() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<Log>();
+        world.register_state::<GameState>(StateConfig::empty().with_initial_transitions(true));
+        world.register_state_schedules::<GameState>();
+        let mut schedule = Schedule::new(EnterSchedule(GameState::Menu));
+        schedule.add_systems(log("enter menu"));
+        world.resource_mut::<Schedules>().insert(schedule);
+
+        world.init_state(None, GameState::Menu);
+        assert!(world.resource::<Log>().0.is_empty());
+
+        // Initial value is entered once, during the state update.
+        world.run_schedule(StateUpdates);
+        world.run_schedule(StateUpdates);
+        assert_eq!(world.resource::<Log>().0, vec!["enter menu"]);
+    }
+
+    #[test]
+    fn transition_schedules_after_exits() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.init_resource::<Log>();
+        world.register_state::<GameState>(StateConfig::empty());
+        world.register_state::<Pause>(StateConfig::empty());
+        world.register_state_schedules::<GameState>();
+        world.register_state_schedules::<Pause>();
+        let mut schedules = world.resource_mut::<Schedules>();
+        let mut schedule = Schedule::new(ExitSchedule(GameState::Menu));
+        schedule.add_systems(log("exit menu"));
+        schedules.insert(schedule);
+        let mut schedule = Schedule::new(TransitionSchedule {
+            exited: None,
+            entered: Some(Pause::Running),
+        });
+        schedule.add_systems(log("start pause"));
+        schedules.insert(schedule);
+        world.init_state(None, GameState::Menu);
+        world.init_state(None, None::<Pause>);
+
+        // Transitions of child states run after exits of parent states.
+        world.update_state(None, GameState::InGame);
+        world.run_schedule(StateUpdates);
+        assert_eq!(world.resource::<Log>().0, vec!["exit menu", "start pause"]);
+    }
+}
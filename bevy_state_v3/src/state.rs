@@ -3,18 +3,25 @@
 use core::fmt::Debug;
 
 use bevy_ecs::{
-    query::{QuerySingleError, With},
-    schedule::{IntoScheduleConfigs, Schedules},
-    system::Populated,
+    change_detection::Mut,
+    component::ComponentId,
+    entity::Entity,
+    query::{Has, QuerySingleError, With},
+    schedule::{IntoScheduleConfigs, Schedule, Schedules},
+    system::{Commands, Populated, ResMut, SystemChangeTick},
     world::World,
 };
 use bevy_log::warn;
 
 use crate::{
-    components::{RegisteredState, StateData},
-    config::StateConfig,
+    components::{RegisteredState, StateData, StateRegistration, StateUpdateOverride},
+    config::{StateConfig, add_tie_breaks},
     state_set::{StateSet, StateSetData},
     system_set::{StateSystemSet, StateUpdates},
+    transition_log::{StateTransitionLog, StateTransitionRecord},
+    transitions::OnTransitionRejected,
+    util::GlobalMarker,
+    validation::validate_registration,
 };
 
 /// Trait for states in a hierarchy.
@@ -112,15 +119,45 @@ pub trait State: Sized + Clone + Debug + PartialEq + Send + Sync + 'static {
 
     /// State update order in transition graph.
     /// Never manually overwrite, always use the derived value.
+    /// Proxy states from [`remote`](crate::remote) are the only exception.
     const ORDER: u32 = Self::Dependencies::HIGHEST_ORDER + 1;
 
     /// Update function of this state.
     /// Implement manually for custom behavior.
+    /// Can be replaced for specific entities with the [`StateUpdateOverride`] component.
     fn update(
         state: &mut StateData<Self>,
         dependencies: StateSetData<'_, Self::Dependencies>,
     ) -> Self::Repr;
 
+    /// Guard which can veto a transition computed by [`State::update`].
+    /// Only called for requested updates if the next value differs from the current one.
+    /// Values forced by updated dependencies can't be vetoed.
+    ///
+    /// Returning `false` rejects the transition, the state keeps its current value,
+    /// the update data is restored to before the update, the request is discarded
+    /// and [`OnTransitionRejected`] is triggered.
+    /// By default all transitions are allowed.
+    fn guard(
+        _state: &StateData<Self>,
+        _next: &Self::Repr,
+        _dependencies: StateSetData<'_, Self::Dependencies>,
+    ) -> bool {
+        true
+    }
+
+    /// Adds additional systems for this state, called during [`State::register_state`]
+    /// and again whenever the [`StateUpdates`] schedule is rebuilt after unregistering a state.
+    /// Used by proxy states from [`remote`](crate::remote), which synchronize their value from other entities.
+    fn register_systems(_schedule: &mut Schedule, _registration: Entity) {}
+
+    /// Component ids and type names of states on other entities, which this state is synchronized from.
+    /// Used by proxy states from [`remote`](crate::remote), so the remote states are validated
+    /// during registration and show up in the [`StateGraph`](crate::graph::StateGraph).
+    fn remote_dependencies(_world: &mut World) -> Vec<(ComponentId, &'static str)> {
+        Vec::new()
+    }
+
     /// Registers machinery for this state type to work correctly.
     fn register_state(world: &mut World, config: StateConfig) {
         // TODO: check states plugin
@@ -145,48 +182,195 @@ pub trait State: Sized + Clone + Debug + PartialEq + Send + Sync + 'static {
             Err(QuerySingleError::NoEntities(_)) => {}
         }
 
-        world.spawn(RegisteredState::<Self>::default());
+        // Dependencies can still be registered later, but it's most likely a mistake.
+        // `TryStatesExt::try_register_state` returns this error instead.
+        if let Err(error) = validate_registration::<Self>(world) {
+            warn!("{error}");
+        }
+
+        let config = config.validate::<Self>();
+        let registration = StateRegistration::new::<Self>(world, config.clone());
+        let registration = world
+            .spawn((RegisteredState::<Self>::default(), registration))
+            .id();
 
         // Register systems for this state.
-        let mut schedules = world.resource_mut::<Schedules>();
-        let schedule = schedules.entry(StateUpdates);
-        schedule.configure_sets(StateSystemSet::configuration::<Self>());
-
-        schedule
-            .add_systems(Self::update_state_data_system.in_set(StateSystemSet::update::<Self>()));
+        world
+            .get_mut::<StateRegistration>(registration)
+            .unwrap()
+            .systems
+            .push(add_state_systems::<Self>);
+        world.resource_scope(|world, mut schedules: Mut<Schedules>| {
+            let schedule = schedules.entry(StateUpdates);
+            add_state_systems::<Self>(schedule, registration, &config);
+            let mut query = world.query::<&StateRegistration>();
+            let this = query.get(world, registration).unwrap();
+            add_tie_breaks(schedule, this, query.iter(world));
+        });
 
-        config.apply::<Self>(world);
+        config.apply::<Self>(world, registration);
     }
 
     /// System that updates the value of this state.
     fn update_state_data_system(
+        mut commands: Commands,
         mut query: Populated<(
+            Entity,
             &mut StateData<Self>,
             <Self::Dependencies as StateSet>::Query,
+            Has<GlobalMarker>,
+            Option<&StateUpdateOverride<Self>>,
         )>,
+        mut log: Option<ResMut<StateTransitionLog>>,
+        ticks: SystemChangeTick,
     ) {
-        for (mut state, dependencies) in query.iter_mut() {
+        for (entity, mut state, dependencies, is_global, update_override) in query.iter_mut() {
             let dependency_updated = Self::Dependencies::is_updated(&dependencies);
             let state_should_update = state.update.should_update();
-            let should_update = dependency_updated || state_should_update;
-            if state.is_updated != should_update {
-                state.is_updated = should_update;
-            }
-            if should_update {
-                let next = Self::update(&mut state, dependencies);
+            let should_reset = state.reset;
+            let initial_transition = state.initial_transition;
+            let mut should_update =
+                should_reset || initial_transition || dependency_updated || state_should_update;
+            let previous = (log.is_some() && should_update && !initial_transition)
+                .then(|| format!("{:?}", state.current));
+            if should_reset {
+                // Reset bypasses the guard.
+                state.reset = false;
+                let initial = state.initial.clone();
+                let next = if Self::ORDER == 1 {
+                    initial
+                } else {
+                    // Dependent states are recomputed starting from the initial value,
+                    // so they can't be reset to a value their dependencies forbid.
+                    // Proxy states are recomputed too, so they don't go back to a stale remote value.
+                    let current = core::mem::replace(&mut state.current, initial);
+                    let next = match update_override {
+                        Some(update_override) => update_override.apply(&mut state, dependencies),
+                        None => Self::update(&mut state, dependencies),
+                    };
+                    state.current = current;
+                    next
+                };
                 state.inner_update(next);
                 state.update.post_update();
+            } else if initial_transition && !dependency_updated {
+                // Initial value is entered as is, pending update is applied during the next update.
+            } else if should_update {
+                // Only requested updates are guarded, values forced by dependencies are always applied.
+                let guarded = state_should_update && !dependency_updated;
+                let snapshot = guarded.then(|| state.update.clone());
+                let guard_dependencies = Self::Dependencies::reborrow(&dependencies);
+                let next = match update_override {
+                    Some(update_override) => update_override.apply(&mut state, dependencies),
+                    None => Self::update(&mut state, dependencies),
+                };
+                let rejected = guarded
+                    && next != state.current
+                    && !Self::guard(&state, &next, guard_dependencies);
+                if let Some(snapshot) = snapshot.filter(|_| rejected) {
+                    // Update data is restored, so the rejected request leaves no trace in the backend.
+                    state.update = snapshot;
+                    let event = OnTransitionRejected::<Self> {
+                        from: state.current.clone(),
+                        to: next,
+                    };
+                    if is_global {
+                        commands.trigger(event);
+                    } else {
+                        commands.trigger_targets(event, entity);
+                    }
+                    should_update = false;
+                } else {
+                    state.inner_update(next);
+                }
+                state.update.post_update();
+            }
+            if initial_transition {
+                // Initial value is entered without exiting anything.
+                state.initial_transition = false;
+                state.previous = None;
+                state.is_reentrant = false;
+            }
+            if let Some(log) = log.as_deref_mut().filter(|_| should_update) {
+                log.push(StateTransitionRecord {
+                    state: core::any::type_name::<Self>(),
+                    entity,
+                    is_global,
+                    previous,
+                    current: format!("{:?}", state.current),
+                    is_reentrant: state.is_reentrant,
+                    tick: ticks.this_run(),
+                });
+            }
+            if state.is_updated != should_update {
+                state.is_updated = should_update;
             }
         }
     }
 }
 
+/// Adds systems of state `S` to the [`StateUpdates`] schedule.
+fn add_state_systems<S: State>(
+    schedule: &mut Schedule,
+    registration: Entity,
+    config: &StateConfig,
+) {
+    schedule.configure_sets(StateSystemSet::configuration::<S>());
+    schedule.add_systems(
+        S::update_state_data_system
+            .in_set(StateSystemSet::update::<S>())
+            .run_if(StateRegistration::is_active(registration)),
+    );
+    #[cfg(feature = "bevy_time")]
+    schedule.add_systems(
+        crate::delayed::apply_delayed_updates::<S>
+            .in_set(StateSystemSet::update::<S>())
+            .before(S::update_state_data_system)
+            .run_if(StateRegistration::is_active(registration)),
+    );
+    S::register_systems(schedule, registration);
+    config.add_systems::<S>(schedule, registration);
+}
+
+/// Rebuilds the [`StateUpdates`] schedule from systems of the remaining state registrations.
+/// Systems can't be removed from a schedule, so this is how systems of unregistered states are removed.
+///
+/// If the schedule is currently running, it's left as is and systems of unregistered states stay disabled.
+pub(crate) fn rebuild_state_updates(world: &mut World) {
+    world.resource_scope(|world, mut schedules: Mut<Schedules>| {
+        let Some(old) = schedules.remove(StateUpdates) else {
+            return;
+        };
+        let mut schedule = Schedule::new(StateUpdates);
+        schedule.set_executor_kind(old.get_executor_kind());
+        schedule.set_build_settings(old.get_build_settings());
+        let mut registrations = world
+            .query::<(Entity, &StateRegistration)>()
+            .iter(world)
+            .collect::<Vec<_>>();
+        registrations.sort_by_key(|(_, registration)| registration.sequence());
+        for (i, (entity, registration)) in registrations.iter().enumerate() {
+            for add_systems in &registration.systems {
+                add_systems(&mut schedule, *entity, registration.config());
+            }
+            add_tie_breaks(
+                &mut schedule,
+                registration,
+                registrations[..i].iter().map(|(_, other)| *other),
+            );
+        }
+        schedules.insert(schedule);
+    });
+}
+
 /// Types that store state update data.
 /// Implemented by by default for:
 /// - [`()`] - states with no manual updates,
 /// - [`Option<S>`] - states with manual updates,
 /// - [`Option<Option<S>>`] - optional states with manual updates.
-pub trait StateUpdate: Debug + Default + Send + Sync + 'static {
+///
+/// Update data is cloned before a requested update, so it can be restored if [`State::guard`] rejects it.
+pub trait StateUpdate: Debug + Default + Clone + Send + Sync + 'static {
     /// Whether the state should be updated this frame.
     fn should_update(&self) -> bool;
 
@@ -194,6 +378,21 @@ pub trait StateUpdate: Debug + Default + Send + Sync + 'static {
     /// This is a good place for reseting update flags.
     /// It's best to not rely on [`State::update`] to reset flags.
     fn post_update(&mut self);
+
+    /// Merges a newly requested update into the existing update data.
+    /// By default the existing data is overwritten.
+    /// Overwrite this for update data that has to persist between requests.
+    fn merge(&mut self, requested: Self) {
+        *self = requested;
+    }
+
+    /// Clears pending update data and any data accumulated by it, like history.
+    /// Called when the state is reset.
+    /// By default the data is replaced with [`Default`].
+    /// Overwrite this for update data that stores configuration.
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
 }
 
 impl<S: State> StateUpdate for Option<S> {
@@ -234,6 +433,13 @@ pub trait StateRepr: Debug + Clone + PartialEq + Send + Sync + 'static {
     fn into_data(self) -> StateData<Self::State> {
         StateData::new(self)
     }
+
+    /// Value used in place of the current value, when computing the initial value
+    /// of an automatically initialized state.
+    /// Only optional states have one.
+    fn placeholder() -> Option<Self> {
+        None
+    }
 }
 
 impl<S: State<Repr = S>> StateRepr for S {
@@ -242,4 +448,8 @@ impl<S: State<Repr = S>> StateRepr for S {
 
 impl<S: State<Repr = Option<S>>> StateRepr for Option<S> {
     type State = S;
+
+    fn placeholder() -> Option<Self> {
+        Some(None)
+    }
 }
@@ -4,6 +4,7 @@
 use bevy_ecs::{
     component::{ComponentId, ComponentsRegistrator, RequiredComponents},
     query::QueryData,
+    world::World,
 };
 use variadics_please::all_tuples;
 
@@ -30,8 +31,19 @@ pub trait StateSet {
         inheritance_depth: u16,
     );
 
+    /// Registers state data components of all states in the set and returns their ids.
+    fn component_ids(world: &mut World) -> Vec<ComponentId>;
+
+    /// Returns type names of all states in the set.
+    fn type_names() -> Vec<&'static str>;
+
     /// Returns whether any of the dependencies updated in last update schedule.
     fn is_updated(set: &<Self::Query as QueryData>::Item<'_>) -> bool;
+
+    /// Copies the set of shared references to state data.
+    fn reborrow<'w>(
+        set: &<Self::Query as QueryData>::Item<'w>,
+    ) -> <Self::Query as QueryData>::Item<'w>;
 }
 
 /// Helper function for panicking if parent state data component is missing.
@@ -54,9 +66,23 @@ impl<S1: State> StateSet for S1 {
         required_components.register(components, panic_missing_state::<S1>, inheritance_depth);
     }
 
+    fn component_ids(world: &mut World) -> Vec<ComponentId> {
+        vec![world.register_component::<StateData<S1>>()]
+    }
+
+    fn type_names() -> Vec<&'static str> {
+        vec![core::any::type_name::<S1>()]
+    }
+
     fn is_updated(s1: &<Self::Query as QueryData>::Item<'_>) -> bool {
         s1.is_updated
     }
+
+    fn reborrow<'w>(
+        s1: &<Self::Query as QueryData>::Item<'w>,
+    ) -> <Self::Query as QueryData>::Item<'w> {
+        *s1
+    }
 }
 
 /// Helper function for compile time max.
@@ -92,9 +118,23 @@ macro_rules! impl_state_set {
                 $(_required_components.register(_components, panic_missing_state::<$type>, _inheritance_depth);)*
             }
 
+            fn component_ids(_world: &mut World) -> Vec<ComponentId> {
+                vec![$(_world.register_component::<StateData<$type>>()),*]
+            }
+
+            fn type_names() -> Vec<&'static str> {
+                vec![$(core::any::type_name::<$type>()),*]
+            }
+
             fn is_updated(($($var,)*): &<Self::Query as QueryData>::Item<'_>) -> bool {
                 $($var.is_updated ||)* false
             }
+
+            fn reborrow<'w>(
+                ($($var,)*): &<Self::Query as QueryData>::Item<'w>,
+            ) -> <Self::Query as QueryData>::Item<'w> {
+                ($(*$var,)*)
+            }
         }
     };
 }
@@ -1,5 +1,7 @@
 //! System set for scheduling state transitions.
 
+use core::any::TypeId;
+
 use bevy_ecs::schedule::{
     InternedSystemSet, IntoScheduleConfigs, ScheduleConfigs, ScheduleLabel, SystemSet,
 };
@@ -14,7 +16,11 @@ pub struct StateUpdates;
 
 /// Updates run from root states to leaf states.
 /// Exits run from leaf states to root states.
+/// Transitions run from root states to leaf states.
 /// Enters run from root states to leaf states.
+///
+/// States with the same order run in no particular order,
+/// unless registered with [`StateConfig::with_deterministic_order`](crate::config::StateConfig::with_deterministic_order).
 #[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum StateSystemSet {
     /// All [`Update`]s.
@@ -25,43 +31,88 @@ pub enum StateSystemSet {
     AllExits,
     /// Higher values then lower ones.
     Exit(u32),
+    /// All [`Transition`]s.
+    AllTransitions,
+    /// Same as [`Update`], lower values before higher ones.
+    Transition(u32),
     /// All [`Enter`]s.
     AllEnters,
     /// Same as [`Update`], lower values before higher ones.
     Enter(u32),
+    /// Update of a single state, part of its [`Update`].
+    UpdateState(TypeId),
+    /// Exit of a single state, part of its [`Exit`].
+    ExitState(TypeId),
+    /// Transition of a single state, part of its [`Transition`].
+    TransitionState(TypeId),
+    /// Enter of a single state, part of its [`Enter`].
+    EnterState(TypeId),
 }
 
 impl StateSystemSet {
     /// Returns system set used to update this state.
     pub fn update<S: State>() -> Self {
-        Self::Update(S::ORDER)
+        Self::UpdateState(TypeId::of::<S>())
     }
 
     /// Returns system set used to run exit transitions for this state.
     pub fn exit<S: State>() -> Self {
-        Self::Exit(S::ORDER)
+        Self::ExitState(TypeId::of::<S>())
+    }
+
+    /// Returns system set used to run combined transitions for this state.
+    pub fn transition<S: State>() -> Self {
+        Self::TransitionState(TypeId::of::<S>())
     }
 
     /// Returns system set used to run enter transitions for this state.
     pub fn enter<S: State>() -> Self {
-        Self::Enter(S::ORDER)
+        Self::EnterState(TypeId::of::<S>())
     }
 
     /// Returns system set configuration for this set.
     pub fn configuration<S: State>() -> ScheduleConfigs<InternedSystemSet> {
         (
-            (Self::AllUpdates, Self::AllExits, Self::AllEnters).chain(),
             (
-                Self::update::<S>()
+                Self::AllUpdates,
+                Self::AllExits,
+                Self::AllTransitions,
+                Self::AllEnters,
+            )
+                .chain(),
+            (
+                Self::Update(S::ORDER)
                     .after(Self::Update(S::ORDER - 1))
                     .in_set(Self::AllUpdates),
-                Self::exit::<S>()
+                Self::Exit(S::ORDER)
                     .before(Self::Exit(S::ORDER - 1))
                     .in_set(Self::AllExits),
-                Self::enter::<S>()
+                Self::Transition(S::ORDER)
+                    .after(Self::Transition(S::ORDER - 1))
+                    .in_set(Self::AllTransitions),
+                Self::Enter(S::ORDER)
                     .after(Self::Enter(S::ORDER - 1))
                     .in_set(Self::AllEnters),
             ),
+            (
+                Self::update::<S>().in_set(Self::Update(S::ORDER)),
+                Self::exit::<S>().in_set(Self::Exit(S::ORDER)),
+                Self::transition::<S>().in_set(Self::Transition(S::ORDER)),
+                Self::enter::<S>().in_set(Self::Enter(S::ORDER)),
+            ),
+        )
+            .into_configs()
+    }
+
+    /// Returns configuration which orders state `second` after state `first`,
+    /// both of which have the same order.
+    /// Exits are ordered in reverse.
+    pub(crate) fn tie_break(first: TypeId, second: TypeId) -> ScheduleConfigs<InternedSystemSet> {
+        (
+            Self::UpdateState(second).after(Self::UpdateState(first)),
+            Self::ExitState(second).before(Self::ExitState(first)),
+            Self::TransitionState(second).after(Self::TransitionState(first)),
+            Self::EnterState(second).after(Self::EnterState(first)),
         )
             .into_configs()
     }
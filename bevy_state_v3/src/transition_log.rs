@@ -0,0 +1,200 @@
+//! Opt-in recording of state updates for debugging.
+//!
+//! Updates are recorded only while the [`StateTransitionLog`] resource exists:
+//! ```rs
+//! app.insert_resource(StateTransitionLog::new(256));
+//! // ...
+//! let log = app.world().resource::<StateTransitionLog>();
+//! log.write_to(std::fs::File::create("transitions.log").unwrap()).unwrap();
+//! ```
+//! Records are stored in the order updates were applied,
+//! which makes it easy to find the order of states that share the same [`State::ORDER`].
+
+use core::fmt::{Display, Formatter};
+use std::collections::VecDeque;
+
+use bevy_ecs::{component::Tick, entity::Entity, resource::Resource};
+use disqualified::ShortName;
+
+use crate::state::State;
+
+/// Single state update, recorded by the [`StateTransitionLog`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateTransitionRecord {
+    /// Type name of the state.
+    pub state: &'static str,
+    /// Entity which stores the state.
+    pub entity: Entity,
+    /// Whether this is the global state.
+    pub is_global: bool,
+    /// [`Debug`] representation of the value before the update,
+    /// [`None`] for initial transitions.
+    pub previous: Option<String>,
+    /// [`Debug`] representation of the value after the update.
+    pub current: String,
+    /// Whether the state was reentered.
+    pub is_reentrant: bool,
+    /// Change tick of the system which applied the update.
+    pub tick: Tick,
+}
+
+impl Display for StateTransitionRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "[{}] {} ", self.tick.get(), ShortName(self.state))?;
+        if self.is_global {
+            write!(f, "(global)")?;
+        } else {
+            write!(f, "({})", self.entity)?;
+        }
+        match &self.previous {
+            Some(previous) => write!(f, ": {previous} -> {}", self.current)?,
+            None => write!(f, ": {} (initial)", self.current)?,
+        }
+        if self.is_reentrant {
+            write!(f, " (reentrant)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Bounded log of state updates.
+/// Once full, the oldest records are dropped.
+///
+/// Recording is enabled by inserting this resource and disabled by removing it.
+#[derive(Resource, Debug, Clone)]
+pub struct StateTransitionLog {
+    records: VecDeque<StateTransitionRecord>,
+    capacity: usize,
+}
+
+impl Default for StateTransitionLog {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl StateTransitionLog {
+    /// Creates an empty log which stores up to `capacity` records.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Maximum number of stored records.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of stored records.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns whether there are no records.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Iterates over records, from the oldest to the newest.
+    pub fn records(&self) -> impl Iterator<Item = &StateTransitionRecord> {
+        self.records.iter()
+    }
+
+    /// Iterates over records of state `S`, from the oldest to the newest.
+    pub fn records_of<S: State>(&self) -> impl Iterator<Item = &StateTransitionRecord> {
+        self.records
+            .iter()
+            .filter(|record| record.state == core::any::type_name::<S>())
+    }
+
+    /// Removes all records.
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    /// Adds a record, dropping the oldest one if the log is full.
+    pub fn push(&mut self, record: StateTransitionRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    /// Writes all records to the writer, one per line.
+    pub fn write_to(&self, mut writer: impl std::io::Write) -> std::io::Result<()> {
+        for record in &self.records {
+            writeln!(writer, "{record}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{schedule::Schedules, world::World};
+    use bevy_state_macros::State;
+
+    use crate::{
+        self as bevy_state_v3, commands::CoreStatesExt, config::StateConfig,
+        system_set::StateUpdates, transition_log::StateTransitionLog,
+    };
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Light {
+        Red,
+        Green,
+    }
+
+    #[derive(State, Default, Clone, Debug, PartialEq)]
+    #[dependency(Light = Light::Green)]
+    enum Car {
+        #[default]
+        Driving,
+    }
+
+    #[test]
+    fn transition_log() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Light>(StateConfig::empty());
+        world.register_state::<Car>(StateConfig::empty());
+        let local = world.spawn_empty().id();
+        world.init_state(Some(local), Light::Red);
+        world.init_state(Some(local), None::<Car>);
+
+        // Updates are not recorded without the resource.
+        world.update_state(Some(local), Light::Red);
+        world.run_schedule(StateUpdates);
+
+        world.insert_resource(StateTransitionLog::new(2));
+        world.update_state(Some(local), Light::Red);
+        world.run_schedule(StateUpdates);
+        world.update_state(Some(local), Light::Green);
+        world.run_schedule(StateUpdates);
+
+        let log = world.resource::<StateTransitionLog>();
+        assert_eq!(log.len(), 2);
+        let light = log.records_of::<Light>().collect::<Vec<_>>();
+        assert_eq!(light.len(), 1);
+        assert_eq!(light[0].previous.as_deref(), Some("Red"));
+        assert_eq!(light[0].current, "Green");
+        assert!(!light[0].is_reentrant);
+
+        let mut output = vec![];
+        log.write_to(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().map(|line| line.split_once(' ').unwrap().1);
+        assert_eq!(
+            lines.collect::<Vec<_>>(),
+            vec![
+                format!("Light ({local}): Red -> Green"),
+                format!("Car ({local}): None -> Some(Driving)"),
+            ]
+        );
+    }
+}
@@ -32,6 +32,17 @@ pub fn on_init_transition<S: State>(
     };
 }
 
+/// Observer that schedules the initial value to be entered during next state update.
+/// The resulting transition has no previous value, so only enter transitions are triggered.
+pub fn on_initial_transition<S: State>(
+    trigger: On<Add, StateData<S>>,
+    mut query: Query<&mut StateData<S>>,
+) {
+    let entity = trigger.target().unwrap();
+    let mut state = query.get_mut(entity).unwrap();
+    state.initial_transition = true;
+}
+
 /// Event triggered when state is removed.
 #[derive(Event)]
 pub struct OnDeinit<S: State>(pub S::Repr);
@@ -52,6 +63,15 @@ pub fn on_deinit_transition<S: State>(
     };
 }
 
+/// Event triggered when a state transition was rejected by [`State::guard`].
+#[derive(Event)]
+pub struct OnTransitionRejected<S: State> {
+    /// Current value of the state, which was kept.
+    pub from: S::Repr,
+    /// Rejected value of the state.
+    pub to: S::Repr,
+}
+
 /// Event triggered when a state is exited.
 /// Reentrant transitions are ignored.
 #[derive(Event, Deref)]
@@ -66,7 +86,45 @@ pub fn on_exit_transition<S: State>(
         if !state.is_updated || state.is_reentrant() {
             continue;
         }
-        let event = OnExit::<S>(state.previous().cloned().unwrap());
+        // Initial transitions have nothing to exit.
+        let Some(previous) = state.previous().cloned() else {
+            continue;
+        };
+        let event = OnExit::<S>(previous);
+        if is_global {
+            commands.trigger(event);
+        } else {
+            commands.trigger_targets(event, entity);
+        };
+    }
+}
+
+/// Event triggered between the exit and enter phases of a state update.
+/// Reentrant transitions are included.
+#[derive(Event)]
+pub struct OnTransition<S: State> {
+    /// Previous value of the state, [`None`] if the state had no previous value.
+    pub from: Option<S::Repr>,
+    /// Current value of the state.
+    pub to: S::Repr,
+    /// Whether the state was reentered.
+    pub reentrant: bool,
+}
+
+/// System for triggering combined transition events.
+pub fn on_transition_transition<S: State>(
+    mut commands: Commands,
+    states: Populated<(Entity, &StateData<S>, Has<GlobalMarker>)>,
+) {
+    for (entity, state, is_global) in states.iter() {
+        if !state.is_updated {
+            continue;
+        }
+        let event = OnTransition::<S> {
+            from: state.reentrant_previous().cloned(),
+            to: state.current().clone(),
+            reentrant: state.is_reentrant(),
+        };
         if is_global {
             commands.trigger(event);
         } else {
@@ -112,7 +170,11 @@ pub fn on_reexit_transition<S: State>(
         if !state.is_updated {
             continue;
         }
-        let event = OnReexit::<S>(state.reentrant_previous().cloned().unwrap());
+        // Initial transitions have nothing to exit.
+        let Some(previous) = state.reentrant_previous().cloned() else {
+            continue;
+        };
+        let event = OnReexit::<S>(previous);
         if is_global {
             commands.trigger(event);
         } else {
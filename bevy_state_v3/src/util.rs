@@ -1,6 +1,11 @@
 //! Various utility functions.
 
-use bevy_ecs::{component::Component, query::With, system::Single};
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    query::With,
+    system::{Query, Single},
+};
 
 use crate::{
     components::StateData,
@@ -27,6 +32,99 @@ pub fn state_changed_to<R: StateRepr>(target: R) -> impl Fn(Global<&StateData<R:
     }
 }
 
+/// Run condition.
+/// Returns true if global state changed from the specified target.
+pub fn state_exited_from<R: StateRepr>(target: R) -> impl Fn(Global<&StateData<R::State>>) -> bool {
+    move |state: Global<&StateData<R::State>>| is_exited_from(&state, &target)
+}
+
+/// Run condition.
+/// Returns true if global state was reentered.
+pub fn state_reentered<S: State>(state: Global<&StateData<S>>) -> bool {
+    state.is_updated() && state.is_reentrant()
+}
+
+/// Run condition.
+/// Returns true if global state is set to any of the specified targets.
+pub fn in_any_state<R: StateRepr>(
+    targets: impl IntoIterator<Item = R>,
+) -> impl Fn(Global<&StateData<R::State>>) -> bool {
+    let targets = targets.into_iter().collect::<Vec<_>>();
+    move |state: Global<&StateData<R::State>>| targets.contains(state.current())
+}
+
+/// Run condition.
+/// Returns true if optional global state is currently enabled.
+pub fn state_is_some<S: State<Repr = Option<S>>>(state: Global<&StateData<S>>) -> bool {
+    state.current().is_some()
+}
+
+/// Run condition.
+/// Returns true if global state was initialized.
+/// Unlike other run conditions, this one does not require the state to exist.
+pub fn state_exists<S: State>(state: Option<Global<&StateData<S>>>) -> bool {
+    state.is_some()
+}
+
+/// Run condition.
+/// Returns true if local state of the entity changed from the specified target.
+pub fn local_state_exited_from<R: StateRepr>(
+    entity: Entity,
+    target: R,
+) -> impl Fn(Query<&StateData<R::State>>) -> bool {
+    move |query: Query<&StateData<R::State>>| {
+        query
+            .get(entity)
+            .is_ok_and(|state| is_exited_from(state, &target))
+    }
+}
+
+/// Run condition.
+/// Returns true if local state of the entity was reentered.
+pub fn local_state_reentered<S: State>(entity: Entity) -> impl Fn(Query<&StateData<S>>) -> bool {
+    move |query: Query<&StateData<S>>| {
+        query
+            .get(entity)
+            .is_ok_and(|state| state.is_updated() && state.is_reentrant())
+    }
+}
+
+/// Run condition.
+/// Returns true if local state of the entity is set to any of the specified targets.
+pub fn local_in_any_state<R: StateRepr>(
+    entity: Entity,
+    targets: impl IntoIterator<Item = R>,
+) -> impl Fn(Query<&StateData<R::State>>) -> bool {
+    let targets = targets.into_iter().collect::<Vec<_>>();
+    move |query: Query<&StateData<R::State>>| {
+        query
+            .get(entity)
+            .is_ok_and(|state| targets.contains(state.current()))
+    }
+}
+
+/// Run condition.
+/// Returns true if optional local state of the entity is currently enabled.
+pub fn local_state_is_some<S: State<Repr = Option<S>>>(
+    entity: Entity,
+) -> impl Fn(Query<&StateData<S>>) -> bool {
+    move |query: Query<&StateData<S>>| {
+        query
+            .get(entity)
+            .is_ok_and(|state| state.current().is_some())
+    }
+}
+
+/// Run condition.
+/// Returns true if local state of the entity was initialized.
+pub fn local_state_exists<S: State>(entity: Entity) -> impl Fn(Query<&StateData<S>>) -> bool {
+    move |query: Query<&StateData<S>>| query.contains(entity)
+}
+
+fn is_exited_from<S: State>(state: &StateData<S>, target: &S::Repr) -> bool {
+    state.is_updated() && !state.is_reentrant() && state.previous() == Some(target)
+}
+
 // TODO: Should this stay here?
 
 /// Marker for global entity.
@@ -35,3 +133,104 @@ pub struct GlobalMarker;
 
 /// Query for single global entity.
 pub type Global<'w, D> = Single<'w, D, With<GlobalMarker>>;
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{schedule::Schedules, system::RunSystemOnce, world::World};
+    use bevy_state_macros::State;
+
+    use crate::{
+        self as bevy_state_v3,
+        commands::CoreStatesExt,
+        config::StateConfig,
+        system_set::StateUpdates,
+        util::{
+            in_any_state, local_in_any_state, local_state_exists, local_state_exited_from,
+            local_state_is_some, local_state_reentered, state_exists, state_exited_from,
+            state_is_some, state_reentered,
+        },
+    };
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Light {
+        Red,
+        Yellow,
+        Green,
+    }
+
+    #[derive(State, Default, Clone, Debug, PartialEq)]
+    #[dependency(Light = Light::Green)]
+    enum Walk {
+        #[default]
+        Go,
+    }
+
+    #[test]
+    fn run_conditions() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        world.register_state::<Light>(StateConfig::empty());
+        world.register_state::<Walk>(StateConfig::empty());
+        assert!(!world.run_system_once(state_exists::<Light>).unwrap());
+
+        let local = world.spawn_empty().id();
+        for target in [None, Some(local)] {
+            world.init_state(target, Light::Red);
+            world.init_state(target, None::<Walk>);
+        }
+        assert!(world.run_system_once(state_exists::<Light>).unwrap());
+        assert!(
+            world
+                .run_system_once(local_state_exists::<Light>(local))
+                .unwrap()
+        );
+
+        for target in [None, Some(local)] {
+            world.update_state(target, Light::Green);
+        }
+        world.run_schedule(StateUpdates);
+        assert!(
+            world
+                .run_system_once(state_exited_from(Light::Red))
+                .unwrap()
+        );
+        assert!(
+            world
+                .run_system_once(local_state_exited_from(local, Light::Red))
+                .unwrap()
+        );
+        assert!(
+            world
+                .run_system_once(in_any_state([Light::Yellow, Light::Green]))
+                .unwrap()
+        );
+        assert!(
+            world
+                .run_system_once(local_in_any_state(local, [Light::Green]))
+                .unwrap()
+        );
+        assert!(world.run_system_once(state_is_some::<Walk>).unwrap());
+        assert!(
+            world
+                .run_system_once(local_state_is_some::<Walk>(local))
+                .unwrap()
+        );
+        assert!(!world.run_system_once(state_reentered::<Light>).unwrap());
+
+        for target in [None, Some(local)] {
+            world.update_state(target, Light::Green);
+        }
+        world.run_schedule(StateUpdates);
+        assert!(
+            !world
+                .run_system_once(state_exited_from(Light::Red))
+                .unwrap()
+        );
+        assert!(world.run_system_once(state_reentered::<Light>).unwrap());
+        assert!(
+            world
+                .run_system_once(local_state_reentered::<Light>(local))
+                .unwrap()
+        );
+    }
+}
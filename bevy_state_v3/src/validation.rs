@@ -0,0 +1,264 @@
+//! Validation of state dependencies.
+//!
+//! Missing dependency states would otherwise only panic once the required
+//! [`StateData`](crate::components::StateData) component is constructed.
+
+use std::collections::{HashMap, HashSet};
+
+use bevy_ecs::{
+    archetype::{ArchetypeGeneration, ArchetypeId},
+    change_detection::Ref,
+    component::ComponentId,
+    entity::Entity,
+    system::{Local, Query},
+    world::World,
+};
+use bevy_log::error;
+use disqualified::ShortName;
+
+use crate::{components::StateRegistration, error::StateError, state::State, state_set::StateSet};
+
+/// Checks whether all dependencies of state `S` are registered, including remote dependencies of proxy states.
+pub(crate) fn validate_registration<S: State>(world: &mut World) -> Result<(), StateError> {
+    let registered = world
+        .query::<&StateRegistration>()
+        .iter(world)
+        .map(StateRegistration::component)
+        .collect::<HashSet<_>>();
+    let missing = S::Dependencies::component_ids(world)
+        .into_iter()
+        .zip(S::Dependencies::type_names())
+        .chain(S::remote_dependencies(world))
+        .filter(|(component, _)| !registered.contains(component))
+        .map(|(_, name)| name)
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(StateError::UnregisteredDependencies {
+            state: core::any::type_name::<S>(),
+            missing,
+        })
+    }
+}
+
+/// Checks whether all dependencies of state `S` are present on the entity.
+pub(crate) fn validate_dependencies<S: State>(
+    world: &mut World,
+    entity: Entity,
+) -> Result<(), StateError> {
+    let registrations = world
+        .query::<&StateRegistration>()
+        .iter(world)
+        .map(|r| {
+            (
+                r.component(),
+                (r.name().to_string(), r.dependencies().to_vec()),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    let names = S::Dependencies::type_names()
+        .into_iter()
+        .map(|name| ShortName(name).to_string());
+    let dependencies = S::Dependencies::component_ids(world)
+        .into_iter()
+        .zip(names)
+        .collect::<Vec<_>>();
+    let entity_ref = world.entity(entity);
+
+    let mut chains = vec![];
+    let mut stack = vec![(vec![ShortName::of::<S>().to_string()], dependencies)];
+    while let Some((chain, dependencies)) = stack.pop() {
+        for (component, name) in dependencies {
+            if entity_ref.contains_id(component) {
+                continue;
+            }
+            let mut chain = chain.clone();
+            chain.push(name);
+            // Missing dependency is missing its own dependencies too, unless they are present.
+            let missing_parents = registrations
+                .get(&component)
+                .map(|(_, parents)| {
+                    parents
+                        .iter()
+                        .filter(|parent| !entity_ref.contains_id(**parent))
+                        .map(|parent| {
+                            let name = registrations
+                                .get(parent)
+                                .map_or_else(|| format!("{parent:?}"), |(name, _)| name.clone());
+                            (*parent, name)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            if missing_parents.is_empty() {
+                chains.push(chain);
+            } else {
+                stack.push((chain, missing_parents));
+            }
+        }
+    }
+
+    if chains.is_empty() {
+        Ok(())
+    } else {
+        Err(StateError::MissingDependencies {
+            state: core::any::type_name::<S>(),
+            entity,
+            chains,
+        })
+    }
+}
+
+/// Debug system which reports state entities that are missing dependency states.
+/// Each missing state is reported once per entity, until the entity gets the dependency or is despawned.
+///
+/// Archetypes missing dependencies are cached, so only archetypes created since the last run are checked,
+/// unless states were registered or unregistered in the meantime.
+pub fn audit_state_entities(
+    world: &World,
+    registrations: Query<Ref<StateRegistration>>,
+    mut generation: Local<Option<ArchetypeGeneration>>,
+    mut registered: Local<usize>,
+    mut invalid: Local<Vec<(ArchetypeId, ComponentId, ComponentId)>>,
+    mut reported: Local<HashSet<(Entity, ComponentId)>>,
+) {
+    let count = registrations.iter().count();
+    if *registered != count || registrations.iter().any(|r| r.is_added()) {
+        *registered = count;
+        *generation = None;
+        invalid.clear();
+    }
+
+    let archetypes = world.archetypes();
+    let start = generation.unwrap_or(ArchetypeGeneration::initial());
+    for archetype in archetypes[start..].iter() {
+        for registration in registrations.iter() {
+            if !archetype.contains(registration.component()) {
+                continue;
+            }
+            for dependency in registration.dependencies() {
+                if !archetype.contains(*dependency) {
+                    invalid.push((archetype.id(), registration.component(), *dependency));
+                }
+            }
+        }
+    }
+    *generation = Some(archetypes.generation());
+
+    let name = |component: ComponentId| {
+        registrations
+            .iter()
+            .find(|r| r.component() == component)
+            .map_or_else(
+                || "unregistered state".to_string(),
+                |r| r.name().to_string(),
+            )
+    };
+    let mut missing = HashSet::new();
+    for (archetype, component, dependency) in invalid.iter() {
+        for entity in archetypes[*archetype].entities() {
+            let key = (entity.id(), *dependency);
+            if !missing.insert(key) || reported.contains(&key) {
+                continue;
+            }
+            error!(
+                "Entity {} has state {}, but is missing its dependency {}.",
+                entity.id(),
+                name(*component),
+                name(*dependency)
+            );
+        }
+    }
+    // Despawned entities and entities which got their dependencies are forgotten.
+    *reported = missing;
+}
+
+#[cfg(test)]
+mod tests {
+    use core::any::type_name;
+
+    use bevy_ecs::{query::With, schedule::Schedules, world::World};
+    use bevy_state_macros::State;
+
+    use crate::{
+        self as bevy_state_v3,
+        commands::{CoreStatesExt, TryStatesExt},
+        components::RegisteredState,
+        components::StateData,
+        config::StateConfig,
+        error::StateError,
+        validation::validate_registration,
+    };
+
+    #[derive(State, Clone, Debug, PartialEq)]
+    enum Root {
+        A,
+    }
+
+    #[derive(State, Default, Clone, Debug, PartialEq)]
+    #[dependency(Root = Root::A)]
+    enum Middle {
+        #[default]
+        B,
+    }
+
+    #[derive(State, Default, Clone, Debug, PartialEq)]
+    #[dependency(Middle = Some(Middle::B))]
+    enum Leaf {
+        #[default]
+        C,
+    }
+
+    #[test]
+    fn dependency_validation() {
+        let mut world = World::new();
+        world.init_resource::<Schedules>();
+        assert!(matches!(
+            validate_registration::<Middle>(&mut world),
+            Err(StateError::UnregisteredDependencies { .. })
+        ));
+        assert_eq!(
+            world.try_register_state::<Middle>(StateConfig::empty()),
+            Err(StateError::UnregisteredDependencies {
+                state: type_name::<Middle>(),
+                missing: vec![type_name::<Root>()],
+            })
+        );
+        let mut registered = world.query_filtered::<(), With<RegisteredState<Middle>>>();
+        assert_eq!(registered.iter(&world).count(), 0);
+
+        world.register_state::<Root>(StateConfig::empty());
+        assert_eq!(
+            world.try_register_state::<Middle>(StateConfig::empty()),
+            Ok(())
+        );
+        assert_eq!(
+            world.try_register_state::<Middle>(StateConfig::empty()),
+            Err(StateError::already_registered::<Middle>())
+        );
+        world.register_state::<Leaf>(StateConfig::empty());
+        assert_eq!(validate_registration::<Middle>(&mut world), Ok(()));
+
+        let entity = world.spawn_empty().id();
+        let error = world
+            .try_init_state(Some(entity), None::<Leaf>)
+            .unwrap_err();
+        assert!(!world.entity(entity).contains::<StateData<Leaf>>());
+        let StateError::MissingDependencies { chains, .. } = &error else {
+            panic!("Unexpected error {error}");
+        };
+        assert_eq!(chains, &vec![vec!["Leaf", "Middle", "Root"]]);
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Entity {entity} is missing dependencies of state Leaf: Leaf -> Middle -> Root."
+            )
+        );
+
+        world.try_init_state(Some(entity), Root::A).unwrap();
+        world.try_init_state(Some(entity), None::<Middle>).unwrap();
+        world.try_init_state(Some(entity), None::<Leaf>).unwrap();
+        assert!(world.entity(entity).contains::<StateData<Leaf>>());
+    }
+}